@@ -4,7 +4,10 @@ mod qdirstat;
 // Set backtrace with export RUST_BACKTRACE=1(unix) or set RUST_BACKTRACE=1(cmd) or $Env:RUST_BACKTRACE=1(powershell)
 #[tokio::main]
 pub async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    qdirstat::run();
+    let code = qdirstat::run();
+    if code != 0 {
+        std::process::exit(code);
+    }
 
     Ok(())
 }
\ No newline at end of file
@@ -1,12 +1,36 @@
 mod filesystem_entry;
 mod commands;
+mod owners;
+mod file_types;
+mod age;
+mod size_distribution;
+mod duplicates;
+mod largest_files;
+mod find;
+mod file_category;
+mod treemap;
+mod tui;
+mod cli;
+mod delete;
+mod trash;
+mod cleanups;
+mod config;
+mod report;
+mod check;
+mod script;
+mod navigation;
+mod command_history;
+mod line_editor;
+mod completion;
 
 use filesystem_entry::{
     FileSystemEntry,
-    filesystem_entry_type::FileSystemEntryType,
+    sort_order::SortOrder,
 };
 
 use commands::*;
+use command_history::CommandHistory;
+use line_editor::LineEditor;
 
 use std::io::*;
 use dialoguer::{
@@ -15,21 +39,13 @@ use dialoguer::{
 };
 use console::Term;
 
-fn open_directory(fse: &FileSystemEntry) {
-    let cmd_string;
-    if cfg!(windows) {
-        cmd_string = "explorer";
-    } else if cfg!(osx) {
-        cmd_string = "open";
-    } else if cfg!(unix) {
-        cmd_string = "xdg-open";
-    } else {
-        utils::log_e(format!("Could not match current OS with an open command. Here is the current path: {}", fse.path_string).as_str());
-        return;
-    }
-
+fn open_directory(fse: &FileSystemEntry, opener: &str) -> bool {
     let path = fse.path_string.to_string();
-    std::process::Command::new(cmd_string).arg(path).spawn().unwrap( );
+    if let Err(e) = std::process::Command::new(opener).arg(path).spawn() {
+        utils::log_e(format!("Could not run '{}': {}. Here is the current path: {}", opener, e, fse.path_string).as_str());
+        return false;
+    }
+    true
 }
 
 /// This method will return a vector of all drives which exist on the windows filesystem
@@ -80,50 +96,7 @@ fn get_root_drive() -> String {
     }
 }
 
-// Takes a partial string and attempts to match it across possible_items returning the first match
-fn tab_complete(current_str: &str, possible_items: Vec::<String>) -> String {
-    let mut result = String::new();
-    for item in possible_items {
-        if item.to_ascii_lowercase() == current_str.to_ascii_lowercase() {
-            continue;
-        }
-
-        if item.to_ascii_lowercase().starts_with(current_str.to_ascii_lowercase().as_str()) {
-            result = item;
-            break;
-        }
-    }
-
-    if result.is_empty() {
-        result = current_str.to_string();
-    }
-
-    result
-}
-
-fn tab(cmd: &mut String, current: &FileSystemEntry) {
-    match current.children() {
-        Some(children) => {
-            match cmd.to_command().command {
-                Commands::ChangeDirectory => {
-                    if cmd.len() < 4 {
-                        return;
-                    }
-
-                    // Need to save a pre-tab-completed str for skipping and advancing the tab complete with other options
-                    // at this point we've asserted its a cd command
-                    let tab_completed = tab_complete(&cmd[3..], children.into_iter()
-                    .map(|item: &FileSystemEntry| item.identifier.to_string()).collect());
-                    *cmd = format!("cd {}", tab_completed).to_string();
-                },
-                _ => return // ignore the tab
-            }    
-        },
-        None => {}
-    }
-}
-
-fn get_next_command(cmd: &mut String, _current: &FileSystemEntry) {
+fn get_next_command(cmd: &mut String, _completer: &completion::Completer, history: &mut CommandHistory) {
 
     // the console crate doesn't work when debugging. So I've added this
     // #[cfg(debug_assertions)] / #[allow(unreachable_code)] to have diff logic
@@ -135,59 +108,41 @@ fn get_next_command(cmd: &mut String, _current: &FileSystemEntry) {
             Ok(_bytes_read) => { },
             Err(_) => panic!("Failed to readline")
         }
+        history.add(cmd);
         return;
     }
 
     #[allow(unreachable_code)]
     {
         let mut term = Term::stdout();
+        let mut editor = LineEditor::default();
+        let mut completion : Option<completion::Completion> = None;
         loop {
             match term.read_key() {
+                Ok(key @ console::Key::Tab) | Ok(key @ console::Key::Char('\t')) | Ok(key @ console::Key::BackTab) if !editor.is_searching() => {
+                    if let Some(candidates) = _completer.complete(&mut editor, &mut completion, key == console::Key::BackTab) {
+                        term.clear_line().expect("failed to clear terminal");
+                        term.write_line(editor.line()).expect("failed to write to terminal");
+                        completion::print_candidates(&candidates);
+                    }
+                },
                 Ok(key) => {
-                    match key {
-                        console::Key::Backspace => {
-                            if cmd.is_empty() {
-                                continue;
-                            }
-                            cmd.remove(cmd.len()-1);
-                            term.clear_line().expect("failed to clear terminal");
-                            term.write(cmd.as_bytes()).expect("failed to write to terminal");
-                            continue;
-                        },
-                        console::Key::Tab => {
-                            tab(cmd, _current);
-                            term.clear_line().expect("failed to clear terminal");
-                            term.write(cmd.as_bytes()).expect("failed to write to terminal");
-                        },
-                        console::Key::Enter => {
-                            term.write_line("").expect("failed to write to terminal");
-                            return;
-                        }
-                        console::Key::Char(c) => {
-                            match c {
-                                '\t' => {
-                                    tab(cmd, _current);
-                                    term.clear_line().expect("failed to clear terminal");
-                                    term.write(cmd.as_bytes()).expect("failed to write to terminal");
-                                    continue;
-                                },
-                                _ => {
-                                    cmd.push(c);
-                                    let mut b = [0; 2];
-                                    c.encode_utf8(&mut b);
-                                    term.write(&b).expect("failed to write to terminal");
-                                    continue;
-                                }
-                            }
-                            
-                        },
-                        _ => { continue; }
+                    completion = None;
+                    if editor.handle(key, history.entries()) {
+                        term.clear_line().expect("failed to clear terminal");
+                        term.write_line(editor.line()).expect("failed to write to terminal");
+                        history.add(editor.line());
+                        *cmd = editor.line().to_string();
+                        return;
                     }
                 },
                 Err(e) => {
                     println!("{:?}", e);
                 },
             }
+            term.clear_line().expect("failed to clear terminal");
+            term.write_all(editor.render(history.entries()).as_bytes()).expect("failed to write to terminal");
+            term.move_cursor_left(editor.cursor_offset()).expect("failed to move the cursor");
         }
     }
 }
@@ -196,110 +151,486 @@ fn icmp(a: &String, b: &String) -> bool {
     return a.to_ascii_lowercase() == b.to_ascii_lowercase();
 }
 
+// Names match case-insensitively, but an exact match wins over the others
+fn child_named<'a>(current: &'a FileSystemEntry, name: &str) -> Option<&'a FileSystemEntry> {
+    let children = current.children()?;
+    children.iter().find(|child| child.identifier == name)
+        .or_else(|| children.iter().find(|child| icmp(&child.identifier, &name.to_string())))
+}
+
+// Paths rm and trash apply to: the named entry of the current directory, or else all marked entries
+fn removal_targets(current: &FileSystemEntry, name: &str, marked: &[String]) -> std::result::Result<Vec<String>, String> {
+    if name.is_empty() {
+        if marked.is_empty() {
+            return Err(String::from("Nothing to remove. Name an entry or mark entries first"));
+        }
+        return Ok(marked.to_vec());
+    }
+
+    match child_named(current, name) {
+        Some(entry) => Ok(vec![entry.path_string.to_string()]),
+        None => Err(format!("No entry matches target '{}'", name)),
+    }
+}
+
+fn script_failed(script: &script::Script, line: &str) -> i32 {
+    utils::log_e(format!("{}:{}: '{}' failed, stopping", script.name, script.line, line).as_str());
+    cli::EXIT_FAILURE
+}
+
+// Drops every path that lies inside one of the removed paths
+fn forget_paths(removed: &[String], lists: &mut [&mut Vec<String>]) {
+    for list in lists.iter_mut() {
+        list.retain(|path| !removed.iter().any(|gone| std::path::Path::new(path).starts_with(gone)));
+    }
+}
+
+// The deepest directory of path that is still in the tree
+fn existing_ancestor(root: &FileSystemEntry, path: &str) -> String {
+    let mut path = std::path::Path::new(path);
+    while root.ancestry(path.to_str().unwrap_or_default()).is_none() {
+        match path.parent() {
+            Some(parent) => path = parent,
+            None => return root.path_string.to_string(),
+        }
+    }
+    path.to_str().unwrap_or_default().to_string()
+}
+
+/// Runs the interactive session, or the subcommand given on the command line. Returns the exit code.
 #[allow(dead_code)]
-pub fn run() {
-    utils::log_i("QDirStat Terminal");
+pub fn run() -> i32 {
+    let options = match cli::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            utils::log_to_stderr();
+            utils::log_e(e.as_str());
+            utils::log(cli::USAGE);
+            return cli::EXIT_USAGE;
+        }
+    };
+
+    if options.report.is_some() || options.check.is_some() {
+        utils::log_to_stderr();
+    }
+
+    let (settings, warnings) = config::Settings::load(&options);
+    settings.apply();
+    for warning in warnings {
+        utils::log_w(warning.as_str());
+    }
+
+    if let Some(report) = &options.report {
+        return report::run_report(report, &settings.excludes.value);
+    }
+    if let Some(check) = &options.check {
+        return check::run_check(check, &settings.excludes.value);
+    }
+
+    // Without a terminal to type at, commands come from --script or stdin and the first failing
+    // one ends the session
+    let mut script = match &options.script {
+        Some(path) => match script::Script::open(path) {
+            Ok(script) => Some(script),
+            Err(e) => {
+                utils::log_e(format!("Cannot read script {}: {}", path.display(), e).as_str());
+                return cli::EXIT_FAILURE;
+            }
+        },
+        None if !std::io::stdin().is_terminal() => Some(script::Script::stdin()),
+        None => None,
+    };
+    if options.assume_yes {
+        delete::assume_yes();
+    }
 
-    let mut zipper = Vec::<&FileSystemEntry>::new();
-    let mut visited_entries = Vec::<&String>::new();
+    if script.is_none() {
+        utils::log_i("QDirStat Terminal");
+    }
+
+    let mut visited_entries = Vec::<String>::new();
+    let mut sort_order : SortOrder = settings.sort.value;
     let mut root : FileSystemEntry = FileSystemEntry::from_drive(get_root_drive().as_str());
 
    
-    root.scan();
+    root.scan(&settings.excludes.value);
     
     
-    root.print(&visited_entries);
-    println!("");
+    if script.is_none() {
+        root.print(&visited_entries, &sort_order);
+        println!("");
+    }
 
-    let mut current : &FileSystemEntry = &root;
+    // Commands such as rm edit the tree in place, so the current directory is kept as a path and
+    // looked up again before every command
+    let mut location : String = root.path_string.to_string();
+    let mut marked = Vec::<String>::new();
+    let mut history = navigation::History::new(&location);
+    // Only typed commands are remembered, not those of scripts
+    let mut command_history = if script.is_none() { CommandHistory::load() } else { CommandHistory::default() };
 
     let mut command_string: String = String::new();
 
+    // Paths listed by the last command that numbers its output, for the jump command
+    let mut results = Vec::<String>::new();
+
     loop {
+        command_string.clear();
 
-        get_next_command(&mut command_string, current);
+        let mut zipper = root.ancestry(&location).unwrap_or_else(|| vec![&root]);
+        let current : &FileSystemEntry = zipper.pop().unwrap_or(&root);
 
-        let cmd : Command = command_string.to_command();
+        match script.as_mut() {
+            None => {
+                let completer = completion::Completer { root: &root, location: &location };
+                get_next_command(&mut command_string, &completer, &mut command_history);
+            },
+            Some(script) => match script.next_command() {
+                Ok(Some(line)) => {
+                    utils::log_i(format!("{}:{}> {}", script.name, script.line, line).as_str());
+                    command_string = line;
+                },
+                Ok(None) => return cli::EXIT_OK,
+                Err(e) => {
+                    utils::log_e(format!("Failed to read {}: {}", script.name, e).as_str());
+                    return cli::EXIT_FAILURE;
+                }
+            },
+        }
+
+        let cmd : Command = match command_string.to_command() {
+            Ok(Some(cmd)) => cmd,
+            // An empty line just gives a new prompt
+            Ok(None) => continue,
+            Err(e) => {
+                utils::log_e(e.as_str());
+                match &script {
+                    Some(script) => return script_failed(script, &command_string),
+                    None => continue,
+                }
+            }
+        };
 
-        match cmd.command {
+        // Every command reports whether it worked, the first one that didn't ends a script
+        let succeeded : bool = match cmd.command {
             Commands::Help => {
-                utils::log_i("QDirStat commands");
-                utils::log("\t ls: List current directory");
-                utils::log("\t cd: Change current directory. (e.g. cd .. or cd Program Files)");
-                utils::log("\t scan: Recursive scan from current directory downward [Not Implemented]");
-                utils::log("\t open: Opens current directory in the file explorer");
-                utils::log("\t quit: Quit program");
+                commands::print_help();
+                true
             },
             Commands::Quit => {
                 println!("\n Session terminated.");
-                return;
+                return cli::EXIT_OK;
             }
             Commands::Open => {
-                open_directory(current);
+                open_directory(current, &settings.opener.value)
             },
             Commands::ChangeDirectory => {
-                if cmd.args.len() < 1 {
+                // Unquoted names with spaces still work, e.g. cd Program Files
+                let target : String = cmd.args.join(" ");
+                if target.is_empty() {
                     utils::log_w("Change directory command requires an additional argument.");
+                    false
+                } else {
+                    match navigation::resolve(&root, &location, &target, history.previous()) {
+                        Err(e) => {
+                            utils::log_w(e.as_str());
+                            false
+                        },
+                        Ok(chain) => {
+                            let target = chain.last().copied().unwrap_or(&root);
+                            if !visited_entries.contains(&target.path_string) {
+                                visited_entries.push(target.path_string.to_string());
+                            }
+                            history.navigate(&mut location, &target.path_string);
+                            target.print(&visited_entries, &sort_order);
+                            println!();
+                            true
+                        }
+                    }
                 }
-
-                let target : String = cmd.args[0].to_ascii_lowercase();
-                match target.as_str() {
-                    ".." => {
-                        match zipper.pop() {
+            },
+            Commands::Back | Commands::Forward => {
+                let moved = match cmd.command {
+                    Commands::Back => history.back(&mut location),
+                    _ => history.forward(&mut location),
+                };
+                if !moved {
+                    utils::log_w(format!("Nothing to go {} to", if let Commands::Back = cmd.command { "back" } else { "forward" }).as_str());
+                } else if let Some(entry) = root.ancestry(&location).and_then(|chain| chain.last().copied()) {
+                    entry.print(&visited_entries, &sort_order);
+                    println!();
+                }
+                moved
+            },
+            Commands::Pwd => {
+                utils::log(location.as_str());
+                true
+            },
+            Commands::Recent => {
+                match cmd.args.first() {
+                    None => {
+                        if history.recent().is_empty() {
+                            utils::log("No directories visited yet");
+                        }
+                        for (idx, path) in history.recent().iter().enumerate() {
+                            utils::log(format!("   {:>3}  {}", idx + 1, path).as_str());
+                        }
+                        true
+                    },
+                    Some(number) => {
+                        let target = number.parse::<usize>().ok()
+                            .and_then(|number| number.checked_sub(1))
+                            .and_then(|idx| history.recent().get(idx))
+                            .and_then(|path| root.ancestry(path))
+                            .and_then(|chain| chain.last().copied());
+                        match target {
                             None => {
-                                utils::log_w("No parent directory exists");
+                                utils::log_w("Recent requires the number of a directory it listed (e.g. recent 2)");
+                                false
                             },
-                            Some(entry) => {
-                                current = entry;
-                                current.print(&visited_entries);
+                            Some(target) => {
+                                history.navigate(&mut location, &target.path_string);
+                                target.print(&visited_entries, &sort_order);
+                                println!();
+                                true
                             }
                         }
                     },
+                }
+            },
+            Commands::ListDirectory => {
+                println!("Path: {}", current.path_string);
+                current.print(&visited_entries, &sort_order);
+                println!("");
+                true
+            },
+            Commands::Owners => {
+                match cmd.args.first().map(|a| a.as_str()) {
+                    Some("all") => owners::print_owners(&root),
+                    _ => owners::print_owners(current),
+                }
+                true
+            },
+            Commands::FileTypes => {
+                match cmd.args.first() {
+                    Some(extension) if !extension.is_empty() => {
+                        let count = cmd.args.get(1).and_then(|c| c.parse::<usize>().ok()).unwrap_or(10);
+                        file_types::print_largest_of_type(current, extension, count)
+                    },
                     _ => {
-
-                        if current.children().is_none() {
-                            utils::log_w(format!("No entry matches target '{}'", target).as_str());
-                            return;
+                        file_types::print_file_types(current);
+                        true
+                    },
+                }
+            },
+            Commands::Age => {
+                age::print_age(current);
+                true
+            },
+            Commands::Sizes => {
+                size_distribution::print_size_distribution(current);
+                true
+            },
+            Commands::Duplicates => {
+                duplicates::print_duplicates(current, &mut results);
+                true
+            },
+            Commands::Top => {
+                let count = cmd.args.first()
+                    .and_then(|count| count.parse::<usize>().ok())
+                    .unwrap_or(largest_files::DEFAULT_COUNT);
+                largest_files::print_largest_files(current, count, &mut results);
+                true
+            },
+            Commands::Find => {
+                find::print_find(current, &cmd.args, &mut results)
+            },
+            Commands::Treemap => {
+                match treemap::show_treemap(current) {
+                    Err(e) => {
+                        utils::log_e(format!("Failed to draw treemap: {}", e).as_str());
+                        false
+                    },
+                    Ok(None) => true,
+                    Ok(Some(path)) => {
+                        if let Some(mut chain) = root.ancestry(&path) {
+                            let target = chain.pop().unwrap_or(&root);
+                            if !visited_entries.contains(&target.path_string) {
+                                visited_entries.push(target.path_string.to_string());
+                            }
+                            history.navigate(&mut location, &target.path_string);
+                            target.print(&visited_entries, &sort_order);
+                            println!();
                         }
-                        
-                        let children = current.children().expect("No children");
-                        match children.iter().position(|c| icmp(&c.identifier, &target)) {
-                            None => {
-                                utils::log_w(format!("No entry matches target '{}'", target).as_str());
-                                return; 
-                            },
-                            Some(idx) => { 
-                                let matching_entry = &children[idx];
-                                match matching_entry.entry_type {
-                                    FileSystemEntryType::File => {
-                                        utils::log_w("Change directory target is a file.");
-                                    }
-                                    FileSystemEntryType::Directory => {
-                                        if !visited_entries.contains(&&matching_entry.path_string) {
-                                            visited_entries.push(&&matching_entry.path_string);
-                                        }
-                                        zipper.push(&current);
-                                        current = matching_entry;
-                                        current.print(&visited_entries);
-                                        println!("");
-                                    }
-                                }                    
-                            },
+                        true
+                    }
+                }
+            },
+            Commands::Sort => {
+                if cmd.args.iter().all(|arg| arg.is_empty()) {
+                    utils::log(format!("Sorting by {}", sort_order).as_str());
+                    true
+                } else {
+                    match SortOrder::parse(&cmd.args) {
+                        Err(e) => {
+                            utils::log_w(e.as_str());
+                            false
+                        },
+                        Ok(order) => {
+                            sort_order = order;
+                            current.print(&visited_entries, &sort_order);
+                            println!();
+                            true
                         }
                     }
                 }
             },
-            Commands::ListDirectory => {
-                println!("Path: {}", current.path_string);
-                current.print(&visited_entries);
-                println!("");
+            Commands::Browse => {
+                let mut chain = zipper.clone();
+                chain.push(current);
+                match tui::browse(chain, &sort_order) {
+                    Err(e) => {
+                        utils::log_e(format!("Failed to start the browser: {}", e).as_str());
+                        false
+                    },
+                    Ok(mut chain) => {
+                        let target = chain.pop().unwrap_or(&root);
+                        history.navigate(&mut location, &target.path_string);
+                        target.print(&visited_entries, &sort_order);
+                        println!();
+                        true
+                    }
+                }
+            },
+            Commands::Jump => {
+                let target = cmd.args.first()
+                    .and_then(|number| number.parse::<usize>().ok())
+                    .and_then(|number| number.checked_sub(1))
+                    .and_then(|idx| results.get(idx));
+
+                match target.and_then(|path| root.ancestry(path)) {
+                    None => {
+                        utils::log_w("Jump requires the number of an entry listed by a previous command (e.g. jump 3)");
+                        false
+                    },
+                    Some(mut chain) => {
+                        // Land in the parent so the entry itself shows up in the listing
+                        chain.pop();
+                        let target = chain.pop().unwrap_or(&root);
+                        if !visited_entries.contains(&target.path_string) {
+                            visited_entries.push(target.path_string.to_string());
+                        }
+                        history.navigate(&mut location, &target.path_string);
+                        target.print(&visited_entries, &sort_order);
+                        println!();
+                        true
+                    }
+                }
+            },
+            Commands::Mark => {
+                let name = cmd.args.join(" ");
+                match name.as_str() {
+                    "" => {
+                        if marked.is_empty() {
+                            utils::log("No marked entries");
+                        }
+                        for path in marked.iter() {
+                            utils::log(format!("   {}", path).as_str());
+                        }
+                        true
+                    },
+                    "clear" => {
+                        marked.clear();
+                        utils::log("Cleared all marks");
+                        true
+                    },
+                    _ => match child_named(current, &name) {
+                        None => {
+                            utils::log_w(format!("No entry matches target '{}'", name).as_str());
+                            false
+                        },
+                        Some(entry) => {
+                            if let Some(idx) = marked.iter().position(|path| path == &entry.path_string) {
+                                marked.remove(idx);
+                                utils::log(format!("Unmarked {}", entry.path_string).as_str());
+                            } else {
+                                marked.push(entry.path_string.to_string());
+                                utils::log(format!("Marked {}", entry.path_string).as_str());
+                            }
+                            true
+                        }
+                    },
+                }
+            },
+            Commands::Remove | Commands::Trash => {
+                let name = cmd.args.join(" ");
+                let (removed, succeeded) = match (&cmd.command, cmd.args.first().map(|arg| arg.as_str())) {
+                    (Commands::Trash, Some("list")) => {
+                        trash::print_trash(std::path::Path::new(&location));
+                        (Vec::<String>::new(), true)
+                    },
+                    (Commands::Trash, Some("restore")) => {
+                        let restored = trash::restore_entries(&mut root, std::path::Path::new(&location), &cmd.args[1..], &settings.excludes.value);
+                        (Vec::<String>::new(), restored)
+                    },
+                    _ => match removal_targets(current, &name, &marked) {
+                        Err(e) => {
+                            utils::log_w(e.as_str());
+                            (Vec::<String>::new(), false)
+                        },
+                        Ok(targets) => match cmd.command {
+                            Commands::Trash => trash::trash_entries(&mut root, &targets),
+                            _ => delete::delete_entries(&mut root, &targets),
+                        },
+                    },
+                };
+                if !removed.is_empty() {
+                    forget_paths(&removed, &mut [&mut marked, &mut visited_entries, &mut results]);
+                    let existing = existing_ancestor(&root, &location);
+                    history.navigate(&mut location, &existing);
+                    history.retain(&location, |path| root.ancestry(path).is_some());
+                    if let Some(entry) = root.ancestry(&location).and_then(|chain| chain.last().copied()) {
+                        entry.print(&visited_entries, &sort_order);
+                        println!();
+                    }
+                }
+                succeeded
+            },
+            Commands::Cleanup => {
+                let name = cmd.args.join(" ");
+                if name.is_empty() {
+                    cleanups::print_cleanups(&settings.cleanups.value);
+                    true
+                } else {
+                    let targets = if marked.is_empty() { vec![location.to_string()] } else { marked.clone() };
+                    let succeeded = cleanups::run_cleanup(&mut root, &settings.cleanups.value, &name, &targets, &settings.excludes.value);
+                    marked.retain(|path| root.ancestry(path).is_some());
+                    let existing = existing_ancestor(&root, &location);
+                    history.navigate(&mut location, &existing);
+                    history.retain(&location, |path| root.ancestry(path).is_some());
+                    if let Some(entry) = root.ancestry(&location).and_then(|chain| chain.last().copied()) {
+                        entry.print(&visited_entries, &sort_order);
+                        println!();
+                    }
+                    succeeded
+                }
+            },
+            Commands::Config => {
+                settings.print(&sort_order);
+                true
             },
             Commands::Scan => {
                 // root.scan();
+                true
             },
+        };
+
+        if !succeeded {
+            if let Some(script) = &script {
+                return script_failed(script, &command_string);
+            }
         }
 
-        command_string.clear();
     }
    
 }
\ No newline at end of file
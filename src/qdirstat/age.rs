@@ -0,0 +1,167 @@
+use super::filesystem_entry::{
+    FileSystemEntry,
+    filesystem_entry_type::FileSystemEntryType,
+    filesystem_entry_extensions::*,
+};
+
+use chrono::{DateTime, Datelike, Local};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Default, PartialEq)]
+struct AgeUsage {
+    bytes: u64,
+    files: u64,
+}
+
+fn print_row(label: &str, usage: &AgeUsage, total_bytes: u64) {
+    let percent = if total_bytes == 0 { 0.0 } else { usage.bytes as f64 * 100.0 / total_bytes as f64 };
+    utils::log(format!("   {:<10} {:>10} {:>8} {:>5.1}%", label, usage.bytes.bytes_to_readable(), usage.files, percent).as_str());
+}
+
+#[derive(Default)]
+struct AgeBuckets {
+    years: BTreeMap<i32, AgeUsage>,
+    // Keyed by months since year 0, only the twelve up to the current one
+    months: BTreeMap<i32, AgeUsage>,
+    unknown: AgeUsage,
+    total_bytes: u64,
+}
+
+// Months are counted from year 0 so the last twelve months form a contiguous range
+fn month_number(time: &DateTime<Local>) -> i32 {
+    time.year() * 12 + time.month0() as i32
+}
+
+/// Adds up the files below fse by the year and month they were last modified in
+fn bucket_by_age(fse: &FileSystemEntry, this_month: i32) -> AgeBuckets {
+    let mut buckets = AgeBuckets::default();
+    fse.walk(&mut |entry: &FileSystemEntry| {
+        if let FileSystemEntryType::File = entry.entry_type {
+            buckets.total_bytes += entry.len;
+
+            let modified : DateTime<Local> = match entry.modified {
+                Some(time) => DateTime::<Local>::from(time),
+                None => {
+                    buckets.unknown.bytes += entry.len;
+                    buckets.unknown.files += 1;
+                    return;
+                }
+            };
+
+            let year = buckets.years.entry(modified.year()).or_default();
+            year.bytes += entry.len;
+            year.files += 1;
+
+            let month = month_number(&modified);
+            if month > this_month - 12 && month <= this_month {
+                let usage = buckets.months.entry(month).or_default();
+                usage.bytes += entry.len;
+                usage.files += 1;
+            }
+        }
+    });
+    buckets
+}
+
+/// Prints how much space below fse was last modified in each year and in each of the last
+/// twelve months, followed by the newest modification time of each child
+pub fn print_age(fse: &FileSystemEntry) {
+    let this_month = month_number(&Local::now());
+    let AgeBuckets { years, months, unknown, total_bytes } = bucket_by_age(fse, this_month);
+
+    utils::log("");
+    utils::log(format!("\tAge: {} ({})", fse.path_string, total_bytes.bytes_to_readable()).as_str());
+    utils::log("");
+
+    if years.is_empty() && unknown.files == 0 {
+        utils::log("No files");
+        return;
+    }
+
+    utils::log_i(format!("   {:<10} {:>10} {:>8} {:>6}", "Year", "Size", "Files", "%").as_str());
+    for (year, usage) in years.iter() {
+        print_row(year.to_string().as_str(), usage, total_bytes);
+    }
+    if unknown.files > 0 {
+        print_row("unknown", &unknown, total_bytes);
+    }
+
+    utils::log("");
+    utils::log_i(format!("   {:<10} {:>10} {:>8} {:>6}", "Month", "Size", "Files", "%").as_str());
+    for month in (this_month - 11)..=this_month {
+        let label = format!("{}-{:02}", month.div_euclid(12), month.rem_euclid(12) + 1);
+        match months.get(&month) {
+            Some(usage) => print_row(label.as_str(), usage, total_bytes),
+            None => print_row(label.as_str(), &AgeUsage::default(), total_bytes),
+        }
+    }
+
+    if let Some(children) = fse.children() {
+        if children.is_empty() {
+            return;
+        }
+
+        // Stalest first, so the subtrees that are safe to archive stand out at the top
+        let mut newest : Vec<(&FileSystemEntry, Option<std::time::SystemTime>)> = children.iter()
+            .map(|child| (child, child.newest_modified()))
+            .collect();
+        newest.sort_by_key(|row| row.1);
+
+        utils::log("");
+        utils::log_i("   Newest modification");
+        for (child, modified) in newest {
+            utils::log(format!("   {:<10} {:?}  {} ({})", format_date(modified), child.entry_type, child.identifier, child.len.bytes_to_readable()).as_str());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn modified(year: i32, month: u32) -> Option<std::time::SystemTime> {
+        Some(Local.ymd(year, month, 15).and_hms(12, 0, 0).into())
+    }
+
+    fn tree() -> FileSystemEntry {
+        let mut root = FileSystemEntry::from_paths("/r", &[
+            ("/r/old", FileSystemEntryType::Directory, 0),
+            ("/r/new", FileSystemEntryType::Directory, 0),
+        ]);
+        for (name, path, len, time) in [
+            ("a", "/r/old/a", 100, modified(2019, 3)),
+            ("b", "/r/old/b", 50, modified(2019, 11)),
+            ("c", "/r/new/c", 10, modified(2021, 6)),
+            ("d", "/r/new/d", 1, None),
+        ] {
+            let mut file = FileSystemEntry::new(name, std::path::Path::new(path), FileSystemEntryType::File, len);
+            file.modified = time;
+            root.insert(file);
+        }
+        root
+    }
+
+    #[test]
+    fn test_bucket_by_age() {
+        let root = tree();
+        let this_month = month_number(&Local.ymd(2021, 8, 1).and_hms(0, 0, 0));
+        let buckets = bucket_by_age(&root, this_month);
+
+        assert_eq!(buckets.total_bytes, 161);
+        assert_eq!(buckets.years.get(&2019), Some(&AgeUsage { bytes: 150, files: 2 }));
+        assert_eq!(buckets.years.get(&2021), Some(&AgeUsage { bytes: 10, files: 1 }));
+        assert_eq!(buckets.unknown, AgeUsage { bytes: 1, files: 1 });
+        // Only June 2021 is within the last twelve months
+        assert_eq!(buckets.months.len(), 1);
+        assert_eq!(buckets.months.get(&(this_month - 2)), Some(&AgeUsage { bytes: 10, files: 1 }));
+    }
+
+    #[test]
+    fn test_newest_modified() {
+        let root = tree();
+        let old = root.ancestry("/r/old").and_then(|chain| chain.last().copied()).expect("entry exists");
+        assert_eq!(old.newest_modified(), modified(2019, 11));
+        assert_eq!(root.newest_modified(), modified(2021, 6));
+    }
+}
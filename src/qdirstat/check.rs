@@ -0,0 +1,243 @@
+use super::filesystem_entry::{
+    FileSystemEntry,
+    excludes::Excludes,
+    filesystem_entry_type::FileSystemEntryType,
+    filesystem_entry_extensions::*,
+};
+use super::cli::{EXIT_OK, EXIT_FAILURE, EXIT_VIOLATIONS};
+
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Options of the check subcommand
+#[derive(Debug, Default)]
+pub struct CheckOptions {
+    pub rules: PathBuf,
+    pub path: PathBuf,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Limit {
+    Size(u64),
+    Files(u64),
+}
+
+impl Limit {
+    fn parse(spec: &str) -> Result<Limit, String> {
+        let spec = spec.trim();
+        let count = spec.strip_suffix("files").or_else(|| spec.strip_suffix("file"));
+        match count {
+            Some(count) => count.trim().parse().map(Limit::Files).map_err(|_e| format!("Invalid file count '{}'", spec)),
+            None => readable_to_bytes(spec).map(Limit::Size).ok_or(format!("Invalid size '{}'", spec)),
+        }
+    }
+
+    fn value(&self) -> u64 {
+        match self {
+            Limit::Size(size) | Limit::Files(size) => *size,
+        }
+    }
+
+    fn describe(&self, value: u64) -> String {
+        match self {
+            Limit::Size(_) => value.bytes_to_readable(),
+            Limit::Files(_) => format!("{} files", value),
+        }
+    }
+}
+
+/// One line of the rules file, e.g. target/ <= 5G or **/*.log <= 1000 files
+#[derive(Debug)]
+pub struct Rule {
+    pub line: usize,
+    pub pattern: String,
+    glob: glob::Pattern,
+    // Patterns ending in / only match directories, and each of them is checked on its own.
+    // Any other pattern is checked against the total of everything it matches.
+    each_directory: bool,
+    pub limit: Limit,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Violation {
+    pub line: usize,
+    // The directory for rules checked per directory, otherwise the pattern
+    pub target: String,
+    pub actual: u64,
+    pub limit: Limit,
+}
+
+/// Parses the rules, one per line. Blank lines and lines starting with # are skipped.
+pub fn parse_rules(contents: &str) -> Result<Vec<Rule>, String> {
+    let mut rules = Vec::<Rule>::new();
+    for (index, text) in contents.lines().enumerate() {
+        let line = index + 1;
+        let text = text.trim();
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+
+        let (pattern, limit) = text.rsplit_once("<=").ok_or(format!("line {}: expected 'path <= limit'", line))?;
+        let pattern = pattern.trim();
+        let each_directory = pattern.ends_with('/');
+        let relative = pattern.trim_start_matches("./").trim_start_matches('/').trim_end_matches('/');
+        if relative.is_empty() {
+            return Err(format!("line {}: missing path", line));
+        }
+        let glob = glob::Pattern::new(relative).map_err(|e| format!("line {}: invalid pattern '{}': {}", line, pattern, e))?;
+        let limit = Limit::parse(limit).map_err(|e| format!("line {}: {}", line, e))?;
+
+        rules.push(Rule { line, pattern: pattern.to_string(), glob, each_directory, limit });
+    }
+    Ok(rules)
+}
+
+fn file_count(entry: &FileSystemEntry) -> u64 {
+    match entry.entry_type {
+        FileSystemEntryType::File => 1,
+        FileSystemEntryType::Directory => {
+            let mut count = 0;
+            entry.walk(&mut |child: &FileSystemEntry| {
+                if let FileSystemEntryType::File = child.entry_type {
+                    count += 1;
+                }
+            });
+            count
+        },
+    }
+}
+
+impl Rule {
+    fn matches(&self, entry: &FileSystemEntry, root: &FileSystemEntry) -> bool {
+        if self.each_directory && !matches!(entry.entry_type, FileSystemEntryType::Directory) {
+            return false;
+        }
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..glob::MatchOptions::new()
+        };
+        let relative = entry.relative_to(root).replace(std::path::MAIN_SEPARATOR, "/");
+        self.glob.matches_with(&relative, options)
+    }
+
+    // The outermost entries below entry matching the rule, so nothing is counted twice
+    fn collect<'a>(&self, entry: &'a FileSystemEntry, root: &FileSystemEntry, found: &mut Vec<&'a FileSystemEntry>) {
+        for child in entry.children().into_iter().flatten() {
+            if self.matches(child, root) {
+                found.push(child);
+            } else {
+                self.collect(child, root, found);
+            }
+        }
+    }
+
+    fn measure(&self, entries: &[&FileSystemEntry]) -> u64 {
+        match self.limit {
+            Limit::Size(_) => entries.iter().map(|entry| entry.len).sum(),
+            Limit::Files(_) => entries.iter().map(|entry| file_count(entry)).sum(),
+        }
+    }
+
+    pub fn check(&self, root: &FileSystemEntry) -> Vec<Violation> {
+        let mut matched = Vec::<&FileSystemEntry>::new();
+        self.collect(root, root, &mut matched);
+
+        let measured : Vec<(String, u64)> = if self.each_directory {
+            matched.iter().map(|entry| (format!("{}/", entry.relative_to(root)), self.measure(&[entry]))).collect()
+        } else {
+            vec![(self.pattern.to_string(), self.measure(&matched))]
+        };
+        measured.into_iter()
+            .filter(|(_target, actual)| *actual > self.limit.value())
+            .map(|(target, actual)| Violation { line: self.line, target, actual, limit: self.limit })
+            .collect()
+    }
+}
+
+/// Scans the path, checks it against the rules file and prints the violations. Returns the
+/// exit code, EXIT_VIOLATIONS when any rule is broken.
+pub fn run_check(options: &CheckOptions, excludes: &Excludes) -> i32 {
+    let rules = match std::fs::read_to_string(&options.rules) {
+        Ok(contents) => parse_rules(&contents),
+        Err(e) => Err(e.to_string()),
+    };
+    let rules = match rules {
+        Ok(rules) => rules,
+        Err(e) => {
+            utils::log_e(format!("Cannot read rules from {}: {}", options.rules.display(), e).as_str());
+            return EXIT_FAILURE;
+        }
+    };
+    let root = match std::fs::canonicalize(&options.path).and_then(|path| FileSystemEntry::from_path(&path, excludes)) {
+        Ok(root) => root,
+        Err(e) => {
+            utils::log_e(format!("Failed to scan {}: {}", options.path.display(), e).as_str());
+            return EXIT_FAILURE;
+        }
+    };
+
+    let violations : Vec<Violation> = rules.iter().flat_map(|rule| rule.check(&root)).collect();
+    let mut output = String::new();
+    for violation in violations.iter() {
+        output.push_str(&format!("{}:{}: {} is {}, allowed {}\n", options.rules.display(), violation.line, violation.target,
+            violation.limit.describe(violation.actual), violation.limit.describe(violation.limit.value())));
+    }
+    if violations.is_empty() {
+        output.push_str(&format!("All {} rules pass for {}\n", rules.len(), root.path_string));
+    } else {
+        output.push_str(&format!("{} violations of {} rules in {}\n", violations.len(), rules.len(), root.path_string));
+    }
+
+    if let Err(e) = std::io::stdout().write_all(output.as_bytes()) {
+        utils::log_e(format!("Failed to write the results: {}", e).as_str());
+        return EXIT_FAILURE;
+    }
+    if violations.is_empty() { EXIT_OK } else { EXIT_VIOLATIONS }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> FileSystemEntry {
+        FileSystemEntry::from_paths("/r", &[
+            ("/r/target", FileSystemEntryType::Directory, 0),
+            ("/r/target/app", FileSystemEntryType::File, 700),
+            ("/r/logs", FileSystemEntryType::Directory, 0),
+            ("/r/logs/old", FileSystemEntryType::Directory, 0),
+            ("/r/logs/old/a.log", FileSystemEntryType::File, 200),
+            ("/r/logs/b.log", FileSystemEntryType::File, 100),
+            ("/r/web", FileSystemEntryType::Directory, 0),
+            ("/r/web/target", FileSystemEntryType::Directory, 0),
+            ("/r/web/target/x", FileSystemEntryType::File, 50),
+        ])
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        let rules = parse_rules("# budgets\n\ntarget/ <= 5G\nlogs/** <= 1000 files\n").expect("valid rules");
+        assert_eq!(rules.len(), 2);
+        assert_eq!((rules[0].line, rules[0].limit), (3, Limit::Size(5 * 1024 * 1024 * 1024)));
+        assert_eq!(rules[1].limit, Limit::Files(1000));
+
+        assert!(parse_rules("target/ 5G").is_err());
+        assert!(parse_rules("target/ <= lots").is_err());
+        assert!(parse_rules("<= 1G").is_err());
+    }
+
+    #[test]
+    fn test_check() {
+        let root = tree();
+        let check = |rules: &str| -> Vec<Violation> {
+            parse_rules(rules).expect("valid rules").iter().flat_map(|rule| rule.check(&root)).collect()
+        };
+
+        assert_eq!(check("target/ <= 600"), vec![Violation { line: 1, target: String::from("target/"), actual: 700, limit: Limit::Size(600) }]);
+        assert!(check("target/ <= 700").is_empty());
+        assert_eq!(check("logs/** <= 250")[0].actual, 300);
+        assert_eq!(check("**/*.log <= 1 file")[0].actual, 2);
+        // Each matching directory is checked on its own
+        assert_eq!(check("**/target/ <= 100").len(), 1);
+        assert!(check("missing/ <= 1").is_empty());
+    }
+}
@@ -0,0 +1,245 @@
+use super::filesystem_entry::{
+    FileSystemEntry,
+    excludes::Excludes,
+    filesystem_entry_type::FileSystemEntryType,
+};
+
+use serde::Deserialize;
+use std::path::Path;
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AppliesTo {
+    Files,
+    Dirs,
+    #[default]
+    Both,
+}
+
+/// A named shell command that can be run on entries, configured as a [[cleanup]] table in
+/// config.toml. %p in the command is replaced by the path of the entry, %n by its name.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Cleanup {
+    pub name: String,
+    pub command: String,
+    #[serde(default)]
+    pub applies_to: AppliesTo,
+    // Globs on the entry name, any of them has to match. Empty matches everything.
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+impl Cleanup {
+    fn new(name: &str, command: &str, applies_to: AppliesTo, patterns: &[&str], confirm: bool) -> Cleanup {
+        Cleanup {
+            name: name.to_string(),
+            command: command.to_string(),
+            applies_to,
+            patterns: patterns.iter().map(|pattern| pattern.to_string()).collect(),
+            confirm,
+        }
+    }
+
+    /// Used when the config file doesn't define any cleanups
+    pub fn defaults() -> Vec<Cleanup> {
+        vec![
+            Cleanup::new("git gc", "git gc", AppliesTo::Dirs, &[], false),
+            Cleanup::new("make clean", "make clean", AppliesTo::Dirs, &[], false),
+            Cleanup::new("compress with zstd", "zstd --rm -q %p", AppliesTo::Files, &[], true),
+        ]
+    }
+
+    pub fn applies(&self, entry: &FileSystemEntry) -> bool {
+        let type_matches = matches!((self.applies_to, &entry.entry_type),
+            (AppliesTo::Both, _)
+            | (AppliesTo::Files, FileSystemEntryType::File)
+            | (AppliesTo::Dirs, FileSystemEntryType::Directory));
+        let options = glob::MatchOptions {
+            case_sensitive: false,
+            ..glob::MatchOptions::new()
+        };
+        let name_matches = self.patterns.is_empty() || self.patterns.iter()
+            .filter_map(|pattern| glob::Pattern::new(pattern).ok())
+            .any(|pattern| pattern.matches_with(&entry.identifier, options));
+
+        type_matches && name_matches
+    }
+
+    /// The command with its placeholders replaced by the quoted path and name of entry. Fails
+    /// when they can't be quoted safely.
+    pub fn expand(&self, entry: &FileSystemEntry) -> Result<String, String> {
+        let mut expanded = String::new();
+        let mut chars = self.command.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                expanded.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('p') => expanded.push_str(&shell_quote(&entry.path_string)?),
+                Some('n') => expanded.push_str(&shell_quote(&entry.identifier)?),
+                Some('%') => expanded.push('%'),
+                Some(other) => {
+                    expanded.push('%');
+                    expanded.push(other);
+                },
+                None => expanded.push('%'),
+            }
+        }
+        Ok(expanded)
+    }
+
+    // Directories run the command inside themselves, files in the directory holding them
+    fn working_dir<'a>(&self, entry: &'a FileSystemEntry) -> &'a Path {
+        let path = Path::new(&entry.path_string);
+        match entry.entry_type {
+            FileSystemEntryType::Directory => path,
+            FileSystemEntryType::File => path.parent().unwrap_or(path),
+        }
+    }
+
+    // Runs the command with its output going straight to the terminal
+    fn run_on(&self, entry: &FileSystemEntry, command: &str) -> std::io::Result<std::process::ExitStatus> {
+        let mut shell = if cfg!(windows) {
+            let mut shell = std::process::Command::new("cmd");
+            shell.arg("/C");
+            shell
+        } else {
+            let mut shell = std::process::Command::new("sh");
+            shell.arg("-c");
+            shell
+        };
+        shell.arg(command).current_dir(self.working_dir(entry)).status()
+    }
+}
+
+// cmd has no way to escape a double quote inside a quoted argument
+fn cmd_quote(text: &str) -> Result<String, String> {
+    if text.contains('"') {
+        return Err(format!("{} contains a double quote, which cmd can't quote", text));
+    }
+    Ok(format!("\"{}\"", text))
+}
+
+fn shell_quote(text: &str) -> Result<String, String> {
+    if cfg!(windows) {
+        cmd_quote(text)
+    } else {
+        Ok(format!("'{}'", text.replace('\'', "'\\''")))
+    }
+}
+
+pub fn print_cleanups(cleanups: &[Cleanup]) {
+    utils::log("");
+    utils::log("\tCleanups");
+    utils::log("");
+    for cleanup in cleanups {
+        let patterns = if cleanup.patterns.is_empty() { String::new() } else { format!(" {}", cleanup.patterns.join(" ")) };
+        utils::log(format!("   {:<24} {:<6}{:<12} {}", cleanup.name, format!("{:?}", cleanup.applies_to).to_lowercase(), patterns, cleanup.command).as_str());
+    }
+    utils::log("");
+    utils::log("Use 'cleanup <name>' to run one on the marked entries, or on the current directory when nothing is marked");
+}
+
+/// Runs the cleanup called name on the entries at paths and rescans what they touched. False
+/// when it could not run on all of them or failed on any.
+pub fn run_cleanup(root: &mut FileSystemEntry, cleanups: &[Cleanup], name: &str, paths: &[String], excludes: &Excludes) -> bool {
+    let cleanup = match cleanups.iter().find(|cleanup| cleanup.name.eq_ignore_ascii_case(name)) {
+        Some(cleanup) => cleanup,
+        None => {
+            utils::log_w(format!("No cleanup named '{}'", name).as_str());
+            print_cleanups(cleanups);
+            return false;
+        }
+    };
+
+    let mut succeeded = true;
+    let mut entries = Vec::<&FileSystemEntry>::new();
+    for path in paths {
+        match root.ancestry(path).and_then(|chain| chain.last().copied()) {
+            Some(entry) if cleanup.applies(entry) => entries.push(entry),
+            Some(entry) => {
+                utils::log_w(format!("'{}' does not apply to {}", cleanup.name, entry.path_string).as_str());
+                succeeded = false;
+            },
+            None => {
+                utils::log_w(format!("{} is no longer in the tree", path).as_str());
+                succeeded = false;
+            },
+        }
+    }
+    if entries.is_empty() {
+        return false;
+    }
+    if cleanup.confirm && !super::delete::confirm(&format!("Run '{}' on {} entries?", cleanup.name, entries.len())) {
+        utils::log("Cleanup cancelled");
+        return succeeded;
+    }
+
+    let mut touched = Vec::<String>::new();
+    for entry in entries {
+        let command = match cleanup.expand(entry) {
+            Ok(command) => command,
+            Err(e) => {
+                utils::log_e(format!("Not running '{}' on {}: {}", cleanup.name, entry.path_string, e).as_str());
+                succeeded = false;
+                continue;
+            }
+        };
+        utils::log_i(format!("{}: {}", cleanup.name, command).as_str());
+        let failure = match cleanup.run_on(entry, &command) {
+            Err(e) => Some(format!("Failed to run '{}' on {}: {}", cleanup.name, entry.path_string, e)),
+            Ok(status) if !status.success() => Some(format!("'{}' failed on {} ({})", cleanup.name, entry.path_string, status)),
+            Ok(_status) => None,
+        };
+        if let Some(failure) = failure {
+            utils::log_e(failure.as_str());
+            succeeded = false;
+        }
+        // Even a failed command may have changed things
+        touched.push(cleanup.working_dir(entry).to_string_lossy().to_string());
+    }
+
+    let touched = super::delete::outermost(&touched);
+    for path in touched.iter() {
+        root.rescan(path, excludes);
+    }
+    utils::log_s(format!("Finished '{}', rescanned {} directories", cleanup.name, touched.len()).as_str());
+    succeeded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cleanup() {
+        let config = super::super::config::parse(r#"
+            [[cleanup]]
+            name = "compress logs"
+            command = "gzip %p"
+            applies_to = "files"
+            patterns = ["*.log"]
+        "#).expect("valid config");
+
+        let cleanup = &config.cleanups[0];
+        assert_eq!(cleanup.applies_to, AppliesTo::Files);
+        assert!(!cleanup.confirm);
+
+        let log = FileSystemEntry::new("App.LOG", Path::new("/var/log/it's here/App.LOG"), FileSystemEntryType::File, 1);
+        let dir = FileSystemEntry::new("old.log", Path::new("/var/log/old.log"), FileSystemEntryType::Directory, 1);
+        assert!(cleanup.applies(&log));
+        assert!(!cleanup.applies(&dir));
+        if cfg!(unix) {
+            assert_eq!(cleanup.expand(&log), Ok(String::from("gzip '/var/log/it'\\''s here/App.LOG'")));
+        }
+        assert_eq!(cmd_quote("C:\\Program Files"), Ok(String::from("\"C:\\Program Files\"")));
+        assert!(cmd_quote("say \"hi\"").is_err());
+
+        assert!(super::super::config::parse("[[cleanup]]\nname = \"x\"").is_err());
+        assert!(super::super::config::parse("[[cleanup]]\nname = \"x\"\ncommand = \"y\"\napplies_to = \"links\"").is_err());
+    }
+}
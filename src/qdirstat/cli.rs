@@ -0,0 +1,100 @@
+use super::filesystem_entry::{
+    filesystem_entry_extensions::Units,
+    sort_order::{SortKey, SortOrder},
+};
+use super::report::{ReportFormat, ReportOptions};
+use super::check::CheckOptions;
+
+use std::path::PathBuf;
+
+pub const USAGE: &str = "Usage: rust_playground [--sort size|name|items|mtime|ext] [--asc|--desc] [--units binary|decimal] [--exclude glob]... [--opener command] [--config file] [--script file] [--yes]
+       rust_playground report [path] [--depth n] [--count n] [--format text|json|csv] [options]
+       rust_playground check <rules file> [path] [options]";
+
+pub const EXIT_OK: i32 = 0;
+// Something went wrong at runtime, e.g. the path to scan doesn't exist
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+// check found rules that are broken
+pub const EXIT_VIOLATIONS: i32 = 3;
+
+/// Options given on the command line. Anything left unset falls back to the config file.
+#[derive(Default)]
+pub struct CliOptions {
+    pub sort: Option<SortOrder>,
+    pub units: Option<Units>,
+    pub excludes: Option<Vec<String>>,
+    pub opener: Option<String>,
+    pub config: Option<PathBuf>,
+    // Commands to run instead of reading them from the terminal
+    pub script: Option<PathBuf>,
+    pub assume_yes: bool,
+    // Set when running the report subcommand instead of the interactive session
+    pub report: Option<ReportOptions>,
+    // Set when running the check subcommand
+    pub check: Option<CheckOptions>,
+}
+
+fn parse_count(option: &str, value: &str) -> Result<usize, String> {
+    value.parse().map_err(|_e| format!("Option {} requires a number, got '{}'", option, value))
+}
+
+pub fn parse(args: impl Iterator<Item = String>) -> Result<CliOptions, String> {
+    let mut options = CliOptions::default();
+    let mut sort_key : Option<SortKey> = None;
+    let mut descending : Option<bool> = None;
+
+    let mut args = args.peekable();
+    match args.peek().map(|arg| arg.as_str()) {
+        Some("report") => options.report = Some(ReportOptions::default()),
+        Some("check") => options.check = Some(CheckOptions::default()),
+        _ => {},
+    }
+    let subcommand = options.report.is_some() || options.check.is_some();
+    if subcommand {
+        args.next();
+    }
+
+    let mut positionals = Vec::<String>::new();
+    while let Some(arg) = args.next() {
+        let mut value = |option: &str| args.next().ok_or(format!("Option {} requires a value", option));
+        match (arg.as_str(), options.report.as_mut()) {
+            ("--depth", Some(report)) => report.depth = parse_count(&arg, &value(&arg)?)?,
+            ("--count", Some(report)) => report.count = parse_count(&arg, &value(&arg)?)?,
+            ("--format", Some(report)) => report.format = ReportFormat::parse(&value(&arg)?)?,
+            (positional, _) if subcommand && !positional.starts_with('-') => positionals.push(arg.to_string()),
+            ("--sort", _) => sort_key = Some(SortKey::parse(&value(&arg)?)?),
+            ("--asc", _) => descending = Some(false),
+            ("--desc", _) => descending = Some(true),
+            ("--units", _) => options.units = Some(Units::parse(&value(&arg)?)?),
+            ("--exclude", _) => options.excludes.get_or_insert_with(Vec::<String>::new).push(value(&arg)?),
+            ("--opener", _) => options.opener = Some(value(&arg)?),
+            ("--config", _) => options.config = Some(PathBuf::from(value(&arg)?)),
+            ("--script", _) if !subcommand => options.script = Some(PathBuf::from(value(&arg)?)),
+            ("--yes", _) if !subcommand => options.assume_yes = true,
+            (other, _) => return Err(format!("Unknown argument '{}'", other)),
+        }
+    }
+
+    let mut positionals = positionals.into_iter();
+    if let Some(report) = options.report.as_mut() {
+        report.path = positionals.next().map(PathBuf::from).unwrap_or(report.path.to_path_buf());
+    }
+    if let Some(check) = options.check.as_mut() {
+        check.rules = positionals.next().map(PathBuf::from).ok_or("check requires a rules file")?;
+        check.path = positionals.next().map(PathBuf::from).unwrap_or(PathBuf::from("."));
+    }
+    if let Some(extra) = positionals.next() {
+        return Err(format!("Unexpected argument '{}'", extra));
+    }
+
+    if sort_key.is_some() || descending.is_some() {
+        let mut order = SortOrder::new(sort_key.unwrap_or(SortKey::Size));
+        if let Some(descending) = descending {
+            order.descending = descending;
+        }
+        options.sort = Some(order);
+    }
+
+    Ok(options)
+}
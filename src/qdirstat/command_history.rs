@@ -0,0 +1,101 @@
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Commands typed in earlier sessions and this one, oldest first. Every command is appended to
+/// the history file as soon as it is entered.
+#[derive(Default)]
+pub struct CommandHistory {
+    entries: Vec<String>,
+    path: Option<PathBuf>,
+}
+
+/// $XDG_STATE_HOME/qdirstat/history, falling back to ~/.local/state when XDG_STATE_HOME is unset
+pub fn history_path() -> Option<PathBuf> {
+    let state_home = std::env::var("XDG_STATE_HOME").ok()
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| std::env::var("HOME").ok().map(|home| PathBuf::from(home).join(".local").join("state")))?;
+    Some(state_home.join("qdirstat").join("history"))
+}
+
+impl CommandHistory {
+    const MAX_ENTRIES: usize = 1000;
+
+    /// Reads the history file. A missing file starts an empty history.
+    pub fn load() -> CommandHistory {
+        let mut history = CommandHistory { entries: Vec::<String>::new(), path: history_path() };
+        let path = match &history.path {
+            Some(path) => path.to_path_buf(),
+            None => return history,
+        };
+        match std::fs::read_to_string(&path) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {},
+            Err(e) => utils::log_w(format!("Failed to read the command history {}: {}", path.display(), e).as_str()),
+            Ok(contents) => {
+                history.entries = contents.lines().filter(|line| !line.trim().is_empty()).map(String::from).collect();
+                // The file only ever grows by appending, so trim it down once it gets too long
+                if history.entries.len() > 2 * CommandHistory::MAX_ENTRIES {
+                    history.truncate();
+                    let mut contents = history.entries.join("\n");
+                    contents.push('\n');
+                    if let Err(e) = std::fs::write(&path, contents) {
+                        utils::log_w(format!("Failed to write the command history {}: {}", path.display(), e).as_str());
+                    }
+                }
+                history.truncate();
+            }
+        }
+        history
+    }
+
+    fn truncate(&mut self) {
+        let excess = self.entries.len().saturating_sub(CommandHistory::MAX_ENTRIES);
+        self.entries.drain(..excess);
+    }
+
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    /// Records a command, unless it is empty or repeats the one before
+    pub fn add(&mut self, line: &str) {
+        let line = line.trim();
+        if line.is_empty() || self.entries.last().map(|last| last.as_str()) == Some(line) {
+            return;
+        }
+        self.entries.push(line.to_string());
+        self.truncate();
+
+        if let Some(path) = &self.path {
+            let appended = path.parent().map_or(Ok(()), std::fs::create_dir_all)
+                .and_then(|_| std::fs::OpenOptions::new().create(true).append(true).open(path))
+                .and_then(|mut file| writeln!(file, "{}", line));
+            if let Err(e) = appended {
+                utils::log_w(format!("Failed to save the command history to {}: {}", path.display(), e).as_str());
+                // Don't complain about every command
+                self.path = None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add() {
+        let mut history = CommandHistory::default();
+        history.add("cd tmp\n");
+        history.add("cd tmp");
+        history.add("  ");
+        history.add("ls");
+        assert_eq!(history.entries(), &["cd tmp", "ls"]);
+
+        for idx in 0..CommandHistory::MAX_ENTRIES {
+            history.add(&format!("top {}", idx));
+        }
+        assert_eq!(history.entries().len(), CommandHistory::MAX_ENTRIES);
+        assert_eq!(history.entries()[0], "top 0");
+    }
+}
@@ -1,8 +1,29 @@
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Commands {
     ListDirectory,
     ChangeDirectory,
     Open,
     Scan,
+    Owners,
+    FileTypes,
+    Age,
+    Sizes,
+    Duplicates,
+    Top,
+    Find,
+    Treemap,
+    Browse,
+    Sort,
+    Jump,
+    Mark,
+    Remove,
+    Trash,
+    Cleanup,
+    Config,
+    Back,
+    Forward,
+    Pwd,
+    Recent,
     Help,
     Quit,
 }
@@ -12,66 +33,267 @@ pub struct Command {
     pub args: Vec::<String>,
 }
 
-pub trait ToCommand {
-    fn to_command(&self) -> Command;
+/// A command as typed, with its description for help
+pub struct CommandInfo {
+    pub command: Commands,
+    // The first name is the one shown by help, the others are aliases
+    pub names: &'static [&'static str],
+    pub help: &'static str,
+    pub usage: Option<&'static str>,
 }
 
-impl ToCommand for String {
-    fn to_command(&self) -> Command {
-        let mut cmd = Command {
-            command: Commands::Help,
-            args: Vec::<String>::new(),
-        };
+const fn info(command: Commands, names: &'static [&'static str], help: &'static str) -> CommandInfo {
+    CommandInfo { command, names, help, usage: None }
+}
 
-        let mut string_cmd = str_ext::strip_trailing_newline(self.as_str()).to_string();
-        let args : Vec::<String> = string_cmd.split(" ").map(|s| String::from(s)).collect();
-        if args.len() > 0 {
-            string_cmd = args[0].to_string();
-            cmd.args = args[1..].to_vec();
-        }
-        
-        if string_cmd.eq("q") || string_cmd.eq("quit") || string_cmd.eq("exit") {
-            cmd.command = Commands::Quit;
-        }    
+/// Every command in the order help lists them. Parsing and help both go by this table.
+pub const COMMANDS: &[CommandInfo] = &[
+    info(Commands::ListDirectory, &["ls", "list", "print"], "List current directory with size, percent of parent, item count and last modified date"),
+    info(Commands::ChangeDirectory, &["cd"], "Change current directory to a relative or absolute path, ~ or - for the previous one (e.g. cd ../logs or cd Program Files)"),
+    info(Commands::Scan, &["scan"], "Recursive scan from current directory downward [Not Implemented]"),
+    info(Commands::Open, &["open", "start"], "Opens current directory in the file explorer"),
+    info(Commands::Owners, &["owners"], "Space used per user and group in the current directory (owners all for the whole tree)"),
+    info(Commands::FileTypes, &["types"], "File type statistics by extension (types pdf 20 lists the 20 largest pdf files)"),
+    info(Commands::Age, &["age"], "Space by year and month of last modification, and the newest change in each subtree"),
+    info(Commands::Sizes, &["sizes"], "File size histogram with P50/P90/P99 and the bytes held above each"),
+    info(Commands::Duplicates, &["dupes", "duplicates"], "Find duplicate files in the current directory, largest reclaimable space first"),
+    info(Commands::Top, &["top"], "List the N largest files anywhere below the current directory (e.g. top 50)"),
+    CommandInfo {
+        usage: Some(super::find::USAGE),
+        ..info(Commands::Find, &["find"], "Search the scanned tree by name, size, age and type (e.g. find *.iso -size +1G -mtime +1y)")
+    },
+    info(Commands::Treemap, &["treemap", "map"], "Draw a treemap of the current directory, select a block and press Enter to open it"),
+    info(Commands::Sort, &["sort"], "Order listings by size, name, items, mtime or ext, optionally asc or desc (e.g. sort name)"),
+    info(Commands::Browse, &["tui", "browse"], "Full screen browser with an expandable tree, press q to come back here"),
+    info(Commands::Jump, &["jump"], "Go to the directory containing a numbered result (e.g. jump 3 after top, find or dupes)"),
+    info(Commands::Mark, &["mark"], "Mark or unmark an entry of the current directory (mark lists marks, mark clear drops them)"),
+    info(Commands::Remove, &["rm", "delete"], "Delete an entry of the current directory, or all marked entries, after confirmation"),
+    info(Commands::Trash, &["trash"], "Move an entry, or all marked entries, to the trash (trash list shows it, trash restore 2 undoes)"),
+    info(Commands::Cleanup, &["cleanup"], "Run a configured cleanup such as git gc on the marked entries or the current directory (cleanup lists them)"),
+    info(Commands::Config, &["config"], "Show the active settings and whether they come from the defaults, the config file or the command line"),
+    info(Commands::Back, &["back"], "Go back to the directory you were in before the last move"),
+    info(Commands::Forward, &["forward"], "Go forward again after back"),
+    info(Commands::Pwd, &["pwd"], "Print the path of the current directory"),
+    info(Commands::Recent, &["recent"], "List recently visited directories, most recent first (recent 2 goes to the second)"),
+    info(Commands::Help, &["help", "h", "?"], "Show this list of commands"),
+    info(Commands::Quit, &["quit", "q", "exit"], "Quit program"),
+];
 
-        if string_cmd.eq("ls") || string_cmd.eq("list") || string_cmd.eq("print") {
-            cmd.command =  Commands::ListDirectory;
+impl CommandInfo {
+    /// e.g. "ls: List current directory ... (also list, print)"
+    pub fn help_line(&self) -> String {
+        match &self.names[1..] {
+            [] => format!("{}: {}", self.names[0], self.help),
+            aliases => format!("{}: {} (also {})", self.names[0], self.help, aliases.join(", ")),
         }
+    }
+}
 
-        if string_cmd.eq("cd") {
-            cmd.command =  Commands::ChangeDirectory;
+pub fn print_help() {
+    utils::log_i("QDirStat commands");
+    for info in COMMANDS {
+        utils::log(format!("\t {}", info.help_line()).as_str());
+        if let Some(usage) = info.usage {
+            utils::log(format!("\t\t {}", usage).as_str());
+        }
+    }
+}
 
-            // This is a little hacky too tired to do it right
-            let mut sentence: String = String::new();
-            for word in &args[1..] {
-                if sentence != "".to_string() {
-                    sentence.push(' ')
-                }
-                sentence.push_str(word)
+// Edits needed to turn a into b, where an edit inserts, removes or replaces a char or swaps two
+// neighbouring ones
+fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b) : (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    distances[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            let mut distance = (distances[i - 1][j] + 1).min(distances[i][j - 1] + 1).min(distances[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
             }
-            cmd.args[0] = sentence;
+            distances[i][j] = distance;
         }
+    }
+    distances[a.len()][b.len()]
+}
 
-        if string_cmd.eq("h") || string_cmd.eq("help") || string_cmd.eq("?") {
-            cmd.command =  Commands::Help;
+/// Names of commands close to the unknown name, closest first
+pub fn suggestions(name: &str) -> Vec<&'static str> {
+    let mut close = Vec::<(usize, &'static str)>::new();
+    for info in COMMANDS {
+        let distance = info.names.iter().map(|known| edit_distance(name, known)).min().unwrap_or(usize::MAX);
+        // Allow more typos in longer names, but never so many that anything short matches
+        if distance <= (name.chars().count() / 3).clamp(1, 2) {
+            close.push((distance, info.names[0]));
         }
+    }
+    close.sort_by_key(|(distance, _name)| *distance);
+    close.into_iter().map(|(_distance, name)| name).take(3).collect()
+}
+
+pub trait ToCommand {
+    /// None for an empty line
+    fn to_command(&self) -> Result<Option<Command>, String>;
+}
+
+/// A word of a command line, with the byte offset in the line where it starts
+#[derive(Debug, PartialEq)]
+pub struct Word {
+    pub start: usize,
+    pub text: String,
+}
 
-        if string_cmd.eq("scan") {
-            cmd.command =  Commands::Scan;
+/// How far splitting a line into words got
+pub struct Lexed {
+    pub words: Vec<Word>,
+    // The quote still open at the end of the line, or a backslash with nothing left to escape
+    pub unterminated: Option<char>,
+    // False when the line ends in whitespace rather than in the last word
+    pub in_word: bool,
+}
+
+/// Splits a line into words like a shell does. Whitespace separates words unless it is quoted.
+/// Single quotes keep everything up to the closing quote, double quotes allow \" and \\ inside,
+/// and a backslash outside quotes takes the next character literally.
+pub fn lex(line: &str) -> Lexed {
+    let mut words = Vec::<Word>::new();
+    // None between words, so that "" still gives an empty word
+    let mut word : Option<Word> = None;
+    let mut chars = line.char_indices();
+    while let Some((idx, c)) = chars.next() {
+        if c.is_whitespace() {
+            if let Some(word) = word.take() {
+                words.push(word);
+            }
+            continue;
         }
 
-        if string_cmd.eq("open") || string_cmd.eq("start") {
-            cmd.command =  Commands::Open;
+        let text = &mut word.get_or_insert_with(|| Word { start: idx, text: String::new() }).text;
+        let unterminated = match c {
+            '\'' => loop {
+                match chars.next() {
+                    Some((_idx, '\'')) => break None,
+                    Some((_idx, c)) => text.push(c),
+                    None => break Some('\''),
+                }
+            },
+            '"' => loop {
+                match chars.next() {
+                    Some((_idx, '"')) => break None,
+                    Some((_idx, '\\')) => match chars.next() {
+                        Some((_idx, c)) if c == '"' || c == '\\' => text.push(c),
+                        Some((_idx, c)) => {
+                            text.push('\\');
+                            text.push(c);
+                        },
+                        None => break Some('"'),
+                    },
+                    Some((_idx, c)) => text.push(c),
+                    None => break Some('"'),
+                }
+            },
+            '\\' => match chars.next() {
+                Some((_idx, c)) => {
+                    text.push(c);
+                    None
+                },
+                None => Some('\\'),
+            },
+            c => {
+                text.push(c);
+                None
+            },
+        };
+        if unterminated.is_some() {
+            words.extend(word);
+            return Lexed { words, unterminated, in_word: true };
         }
+    }
+
+    let in_word = word.is_some();
+    words.extend(word);
+    Lexed { words, unterminated: None, in_word }
+}
+
+/// The words of a command line, see lex
+pub fn tokenize(line: &str) -> Result<Vec<String>, String> {
+    let lexed = lex(line);
+    match lexed.unterminated {
+        Some('\'') => Err(String::from("Unterminated single quote")),
+        Some('"') => Err(String::from("Unterminated double quote")),
+        Some(_backslash) => Err(String::from("Nothing to escape after the trailing backslash")),
+        None => Ok(lexed.words.into_iter().map(|word| word.text).collect()),
+    }
+}
+
+impl ToCommand for String {
+    fn to_command(&self) -> Result<Option<Command>, String> {
+        let mut words = tokenize(self)?.into_iter();
+        let name = match words.next() {
+            Some(name) => name,
+            None => return Ok(None),
+        };
 
-        cmd
+        match COMMANDS.iter().find(|info| info.names.contains(&name.as_str())) {
+            Some(info) => Ok(Some(Command { command: info.command, args: words.collect() })),
+            None => match suggestions(&name).as_slice() {
+                [] => Err(format!("Unknown command '{}'. Type help for the list of commands", name)),
+                close => Err(format!("Unknown command '{}'. Did you mean {}?", name, close.join(" or "))),
+            },
+        }
     }
 }
 
-mod str_ext {
-    pub fn strip_trailing_newline(input: &str) -> &str {
-        return input.strip_suffix("\r\n")
-            .or(input.strip_suffix("\n"))
-            .unwrap_or(&input);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(tokenize("  cd   Program Files\n").unwrap(), vec!["cd", "Program", "Files"]);
+        assert_eq!(tokenize("cd \"Program  Files\"").unwrap(), vec!["cd", "Program  Files"]);
+        assert_eq!(tokenize("rm 'it''s' \"say \\\"hi\\\"\" a\\ b \"\"").unwrap(), vec!["rm", "its", "say \"hi\"", "a b", ""]);
+        assert_eq!(tokenize("find 'C:\\Users' \"\\d\"").unwrap(), vec!["find", "C:\\Users", "\\d"]);
+        assert_eq!(tokenize(" ' leading'").unwrap(), vec![" leading"]);
+        assert!(tokenize("").unwrap().is_empty());
+
+        assert!(tokenize("cd 'open").is_err());
+        assert!(tokenize("cd \"open").is_err());
+        assert!(tokenize("cd open\\").is_err());
+    }
+
+    #[test]
+    fn test_lex() {
+        let lexed = lex("rm  'a b");
+        assert_eq!(lexed.words, vec![Word { start: 0, text: String::from("rm") }, Word { start: 4, text: String::from("a b") }]);
+        assert_eq!((lexed.unterminated, lexed.in_word), (Some('\''), true));
+        assert!(!lex("cd a ").in_word);
+    }
+
+    #[test]
+    fn test_to_command() {
+        let cmd = String::from("rm 'a b'").to_command().unwrap().expect("a command");
+        assert_eq!((cmd.command, cmd.args), (Commands::Remove, vec![String::from("a b")]));
+        assert_eq!(String::from("exit").to_command().unwrap().map(|cmd| cmd.command), Some(Commands::Quit));
+        assert!(String::from("  \n").to_command().unwrap().is_none());
+
+        assert_eq!(String::from("tpo").to_command().err(), Some(String::from("Unknown command 'tpo'. Did you mean top?")));
+        assert_eq!(suggestions("dupse"), vec!["dupes"]);
+        assert!(suggestions("xyzzy").is_empty());
+    }
+
+    #[test]
+    fn test_readme_lists_every_command() {
+        let readme = include_str!("../../readme.md");
+        for info in COMMANDS {
+            assert!(readme.contains(&format!("    {}\n", info.help_line())), "readme is missing {}", info.names[0]);
+            if let Some(usage) = info.usage {
+                assert!(readme.contains(&format!("          {}\n", usage)), "readme is missing the usage of {}", info.names[0]);
+            }
+        }
     }
 }
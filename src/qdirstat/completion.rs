@@ -0,0 +1,312 @@
+use super::commands::{self, Commands, COMMANDS};
+use super::filesystem_entry::{
+    FileSystemEntry,
+    filesystem_entry_type::FileSystemEntryType,
+    sort_order::SortKey,
+};
+use super::find;
+use super::line_editor::LineEditor;
+use super::navigation;
+
+/// A possible completion of the word at the cursor
+#[derive(Clone, Debug, PartialEq)]
+pub struct Candidate {
+    // The whole word, unquoted
+    pub text: String,
+    // What the list of candidates shows, e.g. just the name of a path
+    pub display: String,
+    // Nothing more can follow in the same word, so choosing it ends the word. Directories don't
+    // end it, their entries can be completed next.
+    pub ends_word: bool,
+}
+
+impl Candidate {
+    fn word(text: &str) -> Candidate {
+        Candidate { text: text.to_string(), display: text.to_string(), ends_word: true }
+    }
+}
+
+/// Tab presses in a row on the same word. The first completes the longest common prefix, the
+/// second lists the candidates and every further one cycles through them.
+pub struct Completion {
+    // Byte offset in the line where the word starts
+    start: usize,
+    // The quote the word was started with, kept when it is replaced
+    quote: Option<char>,
+    candidates: Vec<Candidate>,
+    presses: usize,
+    selected: Option<usize>,
+}
+
+/// What completion needs to know about the session
+pub struct Completer<'a> {
+    pub root: &'a FileSystemEntry,
+    pub location: &'a str,
+}
+
+fn matching(words: &[&str], partial: &str) -> Vec<Candidate> {
+    words.iter().filter(|word| word.starts_with(partial)).map(|word| Candidate::word(word)).collect()
+}
+
+/// Quotes text so that tokenize gives it back, in the style the user started the word with
+fn quote(text: &str, quote: Option<char>, ends_word: bool) -> String {
+    let end = if ends_word { " " } else { "" };
+    match quote {
+        Some('\'') => format!("'{}{}", text.replace('\'', "'\\''"), if ends_word { "' " } else { "" }),
+        Some(_double) => format!("\"{}{}", text.replace('\\', "\\\\").replace('"', "\\\""), if ends_word { "\" " } else { "" }),
+        None => {
+            let mut quoted = String::new();
+            for c in text.chars() {
+                if c.is_whitespace() || c == '\'' || c == '"' || c == '\\' {
+                    quoted.push('\\');
+                }
+                quoted.push(c);
+            }
+            quoted + end
+        },
+    }
+}
+
+// Case-insensitive, like the lookup of names in the tree
+fn common_prefix(candidates: &[Candidate]) -> String {
+    let mut prefix : Vec<char> = candidates.first().map(|first| first.text.chars().collect()).unwrap_or_default();
+    for candidate in candidates.iter().skip(1) {
+        let same = prefix.iter().zip(candidate.text.chars())
+            .take_while(|(a, b)| a.to_lowercase().eq(b.to_lowercase()))
+            .count();
+        prefix.truncate(same);
+    }
+    prefix.into_iter().collect()
+}
+
+impl<'a> Completer<'a> {
+    /// Entries below the directory named by partial up to its last /, whose names start with
+    /// the rest of partial
+    fn paths(&self, partial: &str, directories_only: bool) -> Vec<Candidate> {
+        let (directory, name) = match partial.rfind('/') {
+            Some(idx) => partial.split_at(idx + 1),
+            None => ("", partial),
+        };
+        let parent = match directory {
+            "" => self.root.ancestry(self.location).and_then(|chain| chain.last().copied()),
+            directory => navigation::resolve(self.root, self.location, directory, None).ok().and_then(|chain| chain.last().copied()),
+        };
+
+        let name = name.to_lowercase();
+        let mut candidates : Vec<Candidate> = parent.and_then(|parent| parent.children()).into_iter().flatten()
+            .filter(|child| child.identifier.to_lowercase().starts_with(&name))
+            .filter(|child| !directories_only || matches!(child.entry_type, FileSystemEntryType::Directory))
+            .map(|child| {
+                let is_directory = matches!(child.entry_type, FileSystemEntryType::Directory);
+                let display = if is_directory { format!("{}/", child.identifier) } else { child.identifier.to_string() };
+                Candidate { text: format!("{}{}", directory, display), display, ends_word: !is_directory }
+            })
+            .collect();
+        candidates.sort_by(|a, b| a.text.cmp(&b.text));
+        candidates
+    }
+
+    /// Entries of the current directory whose names start with partial, for the commands that
+    /// only take those. A name ends the word, even that of a directory.
+    fn names(&self, partial: &str) -> Vec<Candidate> {
+        let current = self.root.ancestry(self.location).and_then(|chain| chain.last().copied());
+        let partial = partial.to_lowercase();
+        let mut candidates : Vec<Candidate> = current.and_then(|current| current.children()).into_iter().flatten()
+            .filter(|child| child.identifier.to_lowercase().starts_with(&partial))
+            .map(|child| {
+                let display = match child.entry_type {
+                    FileSystemEntryType::Directory => format!("{}/", child.identifier),
+                    _ => child.identifier.to_string(),
+                };
+                Candidate { text: child.identifier.to_string(), display, ends_word: true }
+            })
+            .collect();
+        candidates.sort_by(|a, b| a.text.cmp(&b.text));
+        candidates
+    }
+
+    /// Candidates for the last of words, which is being typed
+    pub fn candidates(&self, words: &[String]) -> Vec<Candidate> {
+        let partial = words.last().map(|word| word.as_str()).unwrap_or_default();
+        if words.len() <= 1 {
+            let mut names : Vec<&str> = COMMANDS.iter().flat_map(|info| info.names.iter().copied()).collect();
+            names.sort_unstable();
+            return matching(&names, partial);
+        }
+
+        let command = COMMANDS.iter().find(|info| info.names.contains(&words[0].as_str())).map(|info| info.command);
+        let previous = words[words.len() - 2].as_str();
+        let first_argument = words.len() == 2;
+        match command {
+            Some(Commands::ChangeDirectory) => self.paths(partial, true),
+            Some(Commands::Remove) => self.names(partial),
+            Some(Commands::Trash) if first_argument => [matching(&["list", "restore"], partial), self.names(partial)].concat(),
+            Some(Commands::Trash) if words[1] != "list" && words[1] != "restore" => self.names(partial),
+            Some(Commands::Mark) if first_argument => [matching(&["clear"], partial), self.names(partial)].concat(),
+            Some(Commands::Owners) if first_argument => matching(&["all"], partial),
+            Some(Commands::Sort) if first_argument => matching(&SortKey::ALL.map(SortKey::name), partial),
+            Some(Commands::Sort) => matching(&["asc", "desc"], partial),
+            Some(Commands::Find) => match previous {
+                "-type" => matching(&["f", "d"], partial),
+                "-sort" => matching(&SortKey::ALL.map(SortKey::name), partial),
+                _ if partial.starts_with('-') => matching(&find::OPTIONS, partial),
+                _ => Vec::<Candidate>::new(),
+            },
+            _ => Vec::<Candidate>::new(),
+        }
+    }
+
+    fn start(&self, editor: &LineEditor) -> Option<Completion> {
+        let before = &editor.line()[..editor.cursor()];
+        let lexed = commands::lex(before);
+        let mut words : Vec<String> = lexed.words.iter().map(|word| word.text.to_string()).collect();
+        let start = match lexed.words.last() {
+            Some(word) if lexed.in_word => word.start,
+            _ => {
+                words.push(String::new());
+                before.len()
+            }
+        };
+        let quote = before[start..].chars().next().filter(|c| *c == '\'' || *c == '"');
+        let candidates = self.candidates(&words);
+        if candidates.is_empty() {
+            return None;
+        }
+        Some(Completion { start, quote, candidates, presses: 0, selected: None })
+    }
+
+    /// Handles a press of Tab, or of Shift-Tab when backwards. state carries over between
+    /// presses in a row and has to be reset when any other key is pressed. Returns the
+    /// candidates when they should be listed.
+    pub fn complete(&self, editor: &mut LineEditor, state: &mut Option<Completion>, backwards: bool) -> Option<Vec<String>> {
+        if state.is_none() {
+            if backwards {
+                return None;
+            }
+            *state = self.start(editor);
+        }
+        let completion = state.as_mut()?;
+        completion.presses += 1;
+
+        match (completion.presses, completion.candidates.as_slice()) {
+            (_, [only]) => {
+                editor.replace_before_cursor(completion.start, &quote(&only.text, completion.quote, only.ends_word));
+                // Starts over, e.g. to go on into the directory just completed
+                *state = None;
+                None
+            },
+            (1, candidates) => {
+                let prefix = common_prefix(candidates);
+                let typed = commands::lex(&editor.line()[completion.start..editor.cursor()]).words.pop().map(|word| word.text).unwrap_or_default();
+                if prefix.chars().count() > typed.chars().count() {
+                    editor.replace_before_cursor(completion.start, &quote(&prefix, completion.quote, false));
+                }
+                None
+            },
+            (2, candidates) => Some(candidates.iter().map(|candidate| candidate.display.to_string()).collect()),
+            (_, candidates) => {
+                let count = candidates.len();
+                let selected = match (completion.selected, backwards) {
+                    (None, false) => 0,
+                    (None, true) => count - 1,
+                    (Some(selected), false) => (selected + 1) % count,
+                    (Some(selected), true) => (selected + count - 1) % count,
+                };
+                completion.selected = Some(selected);
+                editor.replace_before_cursor(completion.start, &quote(&candidates[selected].text, completion.quote, false));
+                None
+            },
+        }
+    }
+}
+
+/// Prints the candidates in columns that fit the terminal
+pub fn print_candidates(candidates: &[String]) {
+    let width = console::Term::stdout().size().1 as usize;
+    let column = candidates.iter().map(|candidate| console::measure_text_width(candidate)).max().unwrap_or(0) + 2;
+    let columns = (width / column).max(1);
+    for row in candidates.chunks(columns) {
+        let cells : Vec<String> = row.iter()
+            .map(|candidate| format!("{}{}", candidate, " ".repeat(column - console::measure_text_width(candidate))))
+            .collect();
+        utils::log(cells.concat().trim_end());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> FileSystemEntry {
+        FileSystemEntry::from_paths("/", &[
+            ("/Program Files", FileSystemEntryType::Directory, 0),
+            ("/Programs", FileSystemEntryType::Directory, 0),
+            ("/Programs/it's.txt", FileSystemEntryType::File, 1),
+            ("/Programs/tools", FileSystemEntryType::Directory, 0),
+            ("/progress.log", FileSystemEntryType::File, 1),
+        ])
+    }
+
+    fn typed(text: &str) -> LineEditor {
+        let mut editor = LineEditor::default();
+        editor.set_line(text.to_string());
+        editor
+    }
+
+    #[test]
+    fn test_candidates() {
+        let root = tree();
+        let completer = Completer { root: &root, location: "/" };
+        let words = |line: &str| -> Vec<String> { line.split(' ').map(String::from).collect() };
+        let texts = |candidates: Vec<Candidate>| -> Vec<String> { candidates.into_iter().map(|candidate| candidate.text).collect() };
+
+        assert_eq!(texts(completer.candidates(&words("tr"))), vec!["trash", "treemap"]);
+        assert_eq!(texts(completer.candidates(&words("cd pro"))), vec!["Program Files/", "Programs/"]);
+        assert_eq!(texts(completer.candidates(&words("rm pro"))), vec!["Program Files", "Programs", "progress.log"]);
+        assert_eq!(texts(completer.candidates(&words("rm Programs/it"))), Vec::<String>::new());
+        assert_eq!(texts(completer.candidates(&words("open pro"))), Vec::<String>::new());
+        assert_eq!(texts(completer.candidates(&words("cd /programs/"))), vec!["/programs/tools/"]);
+        assert_eq!(texts(completer.candidates(&words("find *.iso -type "))), vec!["f", "d"]);
+        assert_eq!(texts(completer.candidates(&words("find -s"))), vec!["-size", "-sort"]);
+        assert_eq!(texts(completer.candidates(&words("mark c"))), vec!["clear"]);
+    }
+
+    #[test]
+    fn test_complete() {
+        let root = tree();
+        let completer = Completer { root: &root, location: "/" };
+        let mut state : Option<Completion> = None;
+
+        let mut editor = typed("cd pro");
+        assert_eq!(completer.complete(&mut editor, &mut state, false), None);
+        assert_eq!(editor.line(), "cd Program");
+        assert_eq!(completer.complete(&mut editor, &mut state, false), Some(vec![String::from("Program Files/"), String::from("Programs/")]));
+        completer.complete(&mut editor, &mut state, false);
+        assert_eq!(editor.line(), "cd Program\\ Files/");
+        completer.complete(&mut editor, &mut state, false);
+        assert_eq!(editor.line(), "cd Programs/");
+        completer.complete(&mut editor, &mut state, true);
+        assert_eq!(editor.line(), "cd Program\\ Files/");
+
+        // A single candidate is taken whole, and a name ends the word
+        let mut state : Option<Completion> = None;
+        let mut editor = typed("rm \"program ");
+        completer.complete(&mut editor, &mut state, false);
+        assert_eq!(editor.line(), "rm \"Program Files\" ");
+        assert!(state.is_none());
+        assert_eq!(commands::tokenize(editor.line()), Ok(vec![String::from("rm"), String::from("Program Files")]));
+
+        // Nested paths only complete for cd, rm takes names in the current directory
+        let completer = Completer { root: &root, location: "/Programs" };
+        let mut editor = typed("rm it");
+        completer.complete(&mut editor, &mut state, false);
+        assert_eq!(commands::tokenize(editor.line()), Ok(vec![String::from("rm"), String::from("it's.txt")]));
+        let mut editor = typed("cd /Programs/to");
+        completer.complete(&mut editor, &mut state, false);
+        assert_eq!(editor.line(), "cd /Programs/tools/");
+
+        let mut editor = typed("zz");
+        assert_eq!(completer.complete(&mut editor, &mut state, false), None);
+        assert_eq!(editor.line(), "zz");
+    }
+}
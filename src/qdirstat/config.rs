@@ -0,0 +1,281 @@
+use super::cleanups::Cleanup;
+use super::cli::CliOptions;
+use super::filesystem_entry::{
+    excludes::Excludes,
+    filesystem_entry_extensions::Units,
+    sort_order::SortOrder,
+};
+
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// config.toml as written by the user. Every key is optional.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub excludes: Option<Vec<String>>,
+    pub sort: Option<String>,
+    pub units: Option<String>,
+    pub opener: Option<String>,
+    pub colors: Option<ColorConfig>,
+    #[serde(rename = "cleanup")]
+    pub cleanups: Vec<Cleanup>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColorConfig {
+    pub normal: Option<String>,
+    pub info: Option<String>,
+    pub success: Option<String>,
+    pub warning: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Source {
+    Default,
+    File,
+    CommandLine,
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::File => write!(f, "config file"),
+            Source::CommandLine => write!(f, "command line"),
+        }
+    }
+}
+
+pub struct Setting<T> {
+    pub value: T,
+    pub source: Source,
+}
+
+impl<T> Setting<T> {
+    fn default(value: T) -> Setting<T> {
+        Setting { value, source: Source::Default }
+    }
+
+    fn set(&mut self, value: T, source: Source) {
+        self.value = value;
+        self.source = source;
+    }
+}
+
+/// The preferences in effect, each with where it came from
+pub struct Settings {
+    pub path: Option<PathBuf>,
+    pub loaded: bool,
+    pub excludes: Setting<Excludes>,
+    pub sort: Setting<SortOrder>,
+    pub units: Setting<Units>,
+    pub opener: Setting<String>,
+    pub colors: Setting<utils::Palette>,
+    pub cleanups: Setting<Vec<Cleanup>>,
+}
+
+/// $XDG_CONFIG_HOME/qdirstat/config.toml, falling back to ~/.config when XDG_CONFIG_HOME is unset
+pub fn config_path() -> Option<PathBuf> {
+    let config_home = std::env::var("XDG_CONFIG_HOME").ok()
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| std::env::var("HOME").ok().map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join("qdirstat").join("config.toml"))
+}
+
+pub fn parse(contents: &str) -> Result<Config, String> {
+    toml::from_str(contents).map_err(|e| e.to_string())
+}
+
+fn default_opener() -> &'static str {
+    if cfg!(windows) {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    }
+}
+
+fn parse_color(name: &str) -> Result<utils::Color, String> {
+    utils::Color::from_str(name).map_err(|e| format!("Invalid color '{}': {}", name, e))
+}
+
+fn parse_palette(colors: &ColorConfig) -> Result<utils::Palette, String> {
+    let mut palette = utils::Palette::default();
+    let fields = [
+        (&colors.normal, &mut palette.normal),
+        (&colors.info, &mut palette.info),
+        (&colors.success, &mut palette.success),
+        (&colors.warning, &mut palette.warning),
+        (&colors.error, &mut palette.error),
+    ];
+    for (name, color) in fields {
+        if let Some(name) = name {
+            *color = parse_color(name)?;
+        }
+    }
+    Ok(palette)
+}
+
+fn parse_sort(spec: &str) -> Result<SortOrder, String> {
+    let args : Vec<String> = spec.split_whitespace().map(String::from).collect();
+    SortOrder::parse(&args)
+}
+
+impl Settings {
+    /// Layers the config file over the defaults and the command line over both. Invalid values
+    /// are skipped and reported in the returned warnings.
+    pub fn resolve(config: Config, cli: &CliOptions) -> (Settings, Vec<String>) {
+        let mut warnings = Vec::<String>::new();
+        let mut settings = Settings {
+            path: None,
+            loaded: false,
+            excludes: Setting::default(Excludes::default()),
+            sort: Setting::default(SortOrder::default()),
+            units: Setting::default(Units::Binary),
+            opener: Setting::default(default_opener().to_string()),
+            colors: Setting::default(utils::Palette::default()),
+            cleanups: Setting::default(Cleanup::defaults()),
+        };
+
+        let mut apply = |result: Result<(), String>| {
+            if let Err(e) = result {
+                warnings.push(e);
+            }
+        };
+        if let Some(excludes) = &config.excludes {
+            apply(Excludes::new(excludes).map(|excludes| settings.excludes.set(excludes, Source::File)));
+        }
+        if let Some(sort) = &config.sort {
+            apply(parse_sort(sort).map(|sort| settings.sort.set(sort, Source::File)));
+        }
+        if let Some(units) = &config.units {
+            apply(Units::parse(units).map(|units| settings.units.set(units, Source::File)));
+        }
+        if let Some(opener) = config.opener {
+            settings.opener.set(opener, Source::File);
+        }
+        if let Some(colors) = &config.colors {
+            apply(parse_palette(colors).map(|palette| settings.colors.set(palette, Source::File)));
+        }
+        if !config.cleanups.is_empty() {
+            settings.cleanups.set(config.cleanups, Source::File);
+        }
+
+        if let Some(excludes) = &cli.excludes {
+            apply(Excludes::new(excludes).map(|excludes| settings.excludes.set(excludes, Source::CommandLine)));
+        }
+        if let Some(sort) = cli.sort {
+            settings.sort.set(sort, Source::CommandLine);
+        }
+        if let Some(units) = cli.units {
+            settings.units.set(units, Source::CommandLine);
+        }
+        if let Some(opener) = &cli.opener {
+            settings.opener.set(opener.to_string(), Source::CommandLine);
+        }
+
+        (settings, warnings)
+    }
+
+    /// Reads the config file, --config or the XDG location, and resolves it against the command
+    /// line. A missing file gives the defaults; a broken one is reported in the warnings and
+    /// ignored so that QDirStat still starts.
+    pub fn load(cli: &CliOptions) -> (Settings, Vec<String>) {
+        let path = cli.config.clone().or_else(config_path);
+        let mut config = Config::default();
+        let mut loaded = false;
+        let mut warnings = Vec::<String>::new();
+        if let Some(path) = &path {
+            match std::fs::read_to_string(path) {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound && cli.config.is_none() => {},
+                Err(e) => warnings.push(format!("Failed to read {}: {}", path.display(), e)),
+                Ok(contents) => match parse(&contents) {
+                    Ok(parsed) => {
+                        config = parsed;
+                        loaded = true;
+                    },
+                    Err(e) => warnings.push(format!("Ignoring {}: {}", path.display(), e)),
+                },
+            }
+        }
+
+        let (mut settings, resolve_warnings) = Settings::resolve(config, cli);
+        warnings.extend(resolve_warnings);
+        settings.path = path;
+        settings.loaded = loaded;
+        (settings, warnings)
+    }
+
+    /// Puts the display preferences into effect
+    pub fn apply(&self) {
+        utils::set_palette(self.colors.value);
+        super::filesystem_entry::filesystem_entry_extensions::set_units(self.units.value);
+    }
+
+    /// Prints every setting with its value and source. sort_order is the order currently in use,
+    /// which the sort command may have changed since startup.
+    pub fn print(&self, sort_order: &SortOrder) {
+        let file = match (&self.path, self.loaded) {
+            (Some(path), true) => format!("{}", path.display()),
+            (Some(path), false) => format!("{} (not found)", path.display()),
+            (None, _) => String::from("none, HOME is not set"),
+        };
+        let palette = &self.colors.value;
+        let colors = format!("normal={:?} info={:?} success={:?} warning={:?} error={:?}",
+            palette.normal, palette.info, palette.success, palette.warning, palette.error).to_lowercase();
+        let cleanups : Vec<&str> = self.cleanups.value.iter().map(|cleanup| cleanup.name.as_str()).collect();
+        let sort_source = if *sort_order == self.sort.value { self.sort.source.to_string() } else { String::from("sort command") };
+
+        utils::log("");
+        utils::log(format!("\tConfiguration: {}", file).as_str());
+        utils::log("");
+        let row = |key: &str, value: String, source: String| {
+            utils::log(format!("   {:<10} {:<40} {}", key, value, source).as_str());
+        };
+        row("sort", sort_order.to_string(), sort_source);
+        row("units", self.units.value.name().to_string(), self.units.source.to_string());
+        row("opener", self.opener.value.to_string(), self.opener.source.to_string());
+        row("excludes", self.excludes.value.patterns().join(", "), self.excludes.source.to_string());
+        row("colors", colors, self.colors.source.to_string());
+        row("cleanups", cleanups.join(", "), self.cleanups.source.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::filesystem_entry::sort_order::SortKey;
+
+    #[test]
+    fn test_resolve_layers() {
+        let config = parse(r#"
+            sort = "name"
+            units = "decimal"
+            excludes = ["node_modules"]
+
+            [colors]
+            warning = "magenta"
+            error = "nope"
+        "#).expect("valid config");
+        let cli = CliOptions { units: Some(Units::Binary), ..CliOptions::default() };
+
+        let (settings, warnings) = Settings::resolve(config, &cli);
+        assert_eq!(settings.sort.value, SortOrder { key: SortKey::Name, descending: false });
+        assert_eq!(settings.sort.source, Source::File);
+        assert_eq!(settings.units.value, Units::Binary);
+        assert_eq!(settings.units.source, Source::CommandLine);
+        assert_eq!(settings.excludes.value.patterns(), vec!["node_modules"]);
+        assert_eq!(settings.opener.source, Source::Default);
+        // The broken color invalidates the whole palette rather than leaving it half applied
+        assert_eq!(settings.colors.source, Source::Default);
+        assert_eq!(warnings.len(), 1);
+
+        assert!(parse("colour = \"red\"").is_err());
+    }
+}
@@ -0,0 +1,145 @@
+use super::filesystem_entry::{
+    FileSystemEntry,
+    filesystem_entry_extensions::*,
+};
+
+use console::Term;
+use dialoguer::{Confirm, theme::ColorfulTheme};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ASSUME_YES: AtomicBool = AtomicBool::new(false);
+
+/// Answers yes to all further confirmations, for scripts started with --yes
+pub fn assume_yes() {
+    ASSUME_YES.store(true, Ordering::Relaxed);
+}
+
+/// Asks a yes/no question, defaulting to no. Without a terminal the answer is read as a line
+/// from stdin so that piped sessions can still confirm.
+pub fn confirm(prompt: &str) -> bool {
+    if ASSUME_YES.load(Ordering::Relaxed) {
+        utils::log(format!("{} yes (--yes)", prompt).as_str());
+        return true;
+    }
+
+    let term = Term::stderr();
+    if !term.is_term() {
+        utils::log(format!("{} [y/N]", prompt).as_str());
+        let mut answer = String::new();
+        return std::io::stdin().read_line(&mut answer).is_ok()
+            && matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes");
+    }
+
+    match Confirm::with_theme(&ColorfulTheme::default()).with_prompt(prompt).default(false).interact_on(&term) {
+        Ok(answer) => answer,
+        Err(e) => {
+            utils::log_e(format!("Failed to read confirmation: {}", e).as_str());
+            false
+        }
+    }
+}
+
+/// Drops paths that lie inside another one of the paths, they go away with their ancestor
+pub fn outermost(paths: &[String]) -> Vec<String> {
+    let mut outermost = Vec::<String>::new();
+    for path in paths {
+        let covered = paths.iter().any(|other| other != path && Path::new(path).starts_with(other));
+        if !covered && !outermost.contains(path) {
+            outermost.push(path.to_string());
+        }
+    }
+    outermost
+}
+
+fn delete_from_disk(path: &str) -> std::io::Result<()> {
+    // Symlinks are removed themselves, never what they point to
+    if std::fs::symlink_metadata(path)?.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+/// Deletes the entries at paths from disk after confirmation and removes them from the tree
+/// below root. Paths that aren't in the tree are left alone, only what the confirmation listed
+/// is deleted. Returns the paths that were deleted, and false when any of them failed; failures
+/// are reported per path. Declining the confirmation is not a failure.
+pub fn delete_entries(root: &mut FileSystemEntry, paths: &[String]) -> (Vec<String>, bool) {
+    let paths = outermost(paths);
+    let entries : Vec<&FileSystemEntry> = paths.iter()
+        .filter_map(|path| root.ancestry(path).and_then(|chain| chain.last().copied()))
+        .collect();
+    if entries.is_empty() {
+        utils::log_w("Nothing to delete");
+        return (Vec::<String>::new(), false);
+    }
+
+    let total : u64 = entries.iter().map(|entry| entry.len).sum();
+    utils::log("");
+    for entry in entries.iter() {
+        utils::log(format!("   {:>10}  {:?}  {}", entry.len.bytes_to_readable(), entry.entry_type, entry.path_string).as_str());
+    }
+    utils::log("");
+
+    let prompt = format!("Permanently delete {} entries ({})?", entries.len(), total.bytes_to_readable());
+    if !confirm(&prompt) {
+        utils::log("Nothing deleted");
+        return (Vec::<String>::new(), true);
+    }
+
+    let listed : Vec<String> = entries.iter().map(|entry| entry.path_string.to_string()).collect();
+    let mut deleted = Vec::<String>::new();
+    let mut freed : u64 = 0;
+    let count = listed.len();
+    for path in listed {
+        match delete_from_disk(&path) {
+            Err(e) => utils::log_e(format!("Failed to delete {}: {}", path, e).as_str()),
+            Ok(()) => {
+                if let Some(removed) = root.remove(&path) {
+                    freed += removed.len;
+                }
+                deleted.push(path);
+            }
+        }
+    }
+
+    utils::log_s(format!("Deleted {} entries, freed {}", deleted.len(), freed.bytes_to_readable()).as_str());
+    let succeeded = deleted.len() == count;
+    (deleted, succeeded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::filesystem_entry::excludes::Excludes;
+
+    #[test]
+    fn test_outermost() {
+        let paths : Vec<String> = ["/a/b", "/a", "/a/b/c", "/ab", "/a"].iter().map(|p| p.to_string()).collect();
+        assert_eq!(outermost(&paths), vec![String::from("/a"), String::from("/ab")]);
+    }
+
+    #[test]
+    fn test_delete_only_listed_entries() {
+        let dir = std::env::temp_dir().join(format!("qdirstat-delete-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("create test directory");
+        std::fs::write(dir.join("listed.txt"), "a").expect("write test file");
+        let mut root = FileSystemEntry::from_drive(dir.to_str().unwrap_or_default());
+        root.scan(&Excludes::none());
+        // Created after the scan, so the tree doesn't know it
+        std::fs::write(dir.join("unknown.txt"), "b").expect("write test file");
+
+        assume_yes();
+        let listed = dir.join("listed.txt").to_string_lossy().to_string();
+        let unknown = dir.join("unknown.txt").to_string_lossy().to_string();
+        let (deleted, succeeded) = delete_entries(&mut root, &[listed.to_string(), unknown]);
+        let unknown_kept = dir.join("unknown.txt").exists();
+        std::fs::remove_dir_all(&dir).expect("remove test directory");
+
+        assert!(root.ancestry(&listed).is_none());
+        assert_eq!(deleted, vec![listed]);
+        assert!(succeeded);
+        assert!(unknown_kept);
+    }
+}
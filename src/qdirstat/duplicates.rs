@@ -0,0 +1,202 @@
+use super::filesystem_entry::{
+    FileSystemEntry,
+    filesystem_entry_type::FileSystemEntryType,
+    filesystem_entry_extensions::*,
+};
+
+use indicatif::{ProgressBar, ProgressStyle};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Only the head of each file is hashed in the first pass; most same-sized files already differ there
+const PARTIAL_HASH_BYTES: u64 = 16 * 1024;
+
+pub struct DuplicateGroup<'a> {
+    pub size: u64,
+    pub files: Vec<&'a FileSystemEntry>,
+}
+
+impl DuplicateGroup<'_> {
+    /// Bytes that would be freed by keeping a single copy
+    pub fn wasted(&self) -> u64 {
+        self.size * (self.files.len() as u64 - 1)
+    }
+}
+
+fn hash_file(path: &str, limit: u64) -> std::io::Result<Vec<u8>> {
+    let mut reader = std::fs::File::open(path)?.take(limit);
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hasher.finalize().to_vec())
+}
+
+// Hashes the first `limit` bytes of every candidate on all cores. Files that can't be read are dropped.
+fn hash_all<'a>(candidates: &[&'a FileSystemEntry], limit: u64, progress: &ProgressBar) -> Vec<(&'a FileSystemEntry, Vec<u8>)> {
+    let next = AtomicUsize::new(0);
+    let workers = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+
+    std::thread::scope(|scope| {
+        let handles : Vec<_> = (0..workers).map(|_| scope.spawn(|| {
+            let mut hashed = Vec::<(&'a FileSystemEntry, Vec<u8>)>::new();
+            while let Some(entry) = candidates.get(next.fetch_add(1, Ordering::Relaxed)) {
+                if let Ok(hash) = hash_file(&entry.path_string, limit) {
+                    hashed.push((entry, hash));
+                }
+                progress.inc(entry.len.min(limit));
+            }
+            hashed
+        })).collect();
+
+        handles.into_iter()
+            .flat_map(|handle| handle.join().expect("Hashing thread panicked"))
+            .collect()
+    })
+}
+
+fn group_by_hash<'a>(hashed: Vec<(&'a FileSystemEntry, Vec<u8>)>) -> Vec<DuplicateGroup<'a>> {
+    let mut groups = HashMap::<(u64, Vec<u8>), Vec<&FileSystemEntry>>::new();
+    for (entry, hash) in hashed {
+        groups.entry((entry.len, hash)).or_default().push(entry);
+    }
+
+    groups.into_iter()
+        .filter(|(_key, files)| files.len() > 1)
+        .map(|((size, _hash), mut files)| {
+            files.sort_by(|a, b| a.path_string.cmp(&b.path_string));
+            DuplicateGroup { size, files }
+        })
+        .collect()
+}
+
+fn hashing_progress(prefix: &'static str, candidates: &[&FileSystemEntry], limit: u64) -> ProgressBar {
+    let total : u64 = candidates.iter().map(|entry| entry.len.min(limit)).sum();
+    let progress = ProgressBar::new(total).with_style(ProgressStyle::default_bar()
+        .template("{prefix} [{bar:40}] {bytes}/{total_bytes} ({eta})")
+        .progress_chars("=> "));
+    progress.set_prefix(prefix);
+    progress
+}
+
+/// Finds files below fse with identical content. Candidates are narrowed down by size, then by a
+/// hash of their first bytes and finally by a hash of their full content.
+pub fn find_duplicates(fse: &FileSystemEntry) -> Vec<DuplicateGroup<'_>> {
+    let mut by_size = HashMap::<u64, Vec<&FileSystemEntry>>::new();
+    fse.walk(&mut |entry: &FileSystemEntry| {
+        if let FileSystemEntryType::File = entry.entry_type {
+            if entry.len > 0 {
+                by_size.entry(entry.len).or_default().push(entry);
+            }
+        }
+    });
+
+    let candidates : Vec<&FileSystemEntry> = by_size.into_values()
+        .filter(|files| files.len() > 1)
+        .flatten()
+        .collect();
+
+    let progress = hashing_progress("Partial hashes", &candidates, PARTIAL_HASH_BYTES);
+    let partial_groups = group_by_hash(hash_all(&candidates, PARTIAL_HASH_BYTES, &progress));
+    progress.finish_and_clear();
+
+    // Small files were read completely by the partial pass, so their groups are final already
+    let (mut duplicates, unconfirmed) : (Vec<DuplicateGroup>, Vec<DuplicateGroup>) = partial_groups.into_iter()
+        .partition(|group| group.size <= PARTIAL_HASH_BYTES);
+
+    let candidates : Vec<&FileSystemEntry> = unconfirmed.into_iter()
+        .flat_map(|group| group.files)
+        .collect();
+
+    let progress = hashing_progress("Full hashes", &candidates, u64::MAX);
+    duplicates.extend(group_by_hash(hash_all(&candidates, u64::MAX, &progress)));
+    progress.finish_and_clear();
+
+    duplicates.sort_by_key(|group| std::cmp::Reverse(group.wasted()));
+    duplicates
+}
+
+/// Prints the duplicate groups below fse, most reclaimable space first. Every listed file is
+/// numbered and results is refilled with their paths so they can be jumped to afterwards.
+pub fn print_duplicates(fse: &FileSystemEntry, results: &mut Vec<String>) {
+    results.clear();
+    let duplicates = find_duplicates(fse);
+    let wasted : u64 = duplicates.iter().map(|group| group.wasted()).sum();
+
+    utils::log("");
+    utils::log(format!("\tDuplicates: {} ({} groups, {} reclaimable)", fse.path_string, duplicates.len(), wasted.bytes_to_readable()).as_str());
+    utils::log("");
+
+    if duplicates.is_empty() {
+        utils::log("No duplicate files");
+        return;
+    }
+
+    for group in duplicates.iter() {
+        utils::log_i(format!("   {} x {} ({} wasted)", group.files.len(), group.size.bytes_to_readable(), group.wasted().bytes_to_readable()).as_str());
+        for file in group.files.iter() {
+            results.push(file.path_string.to_string());
+            utils::log(format!("     [{}] {}", results.len(), file.relative_to(fse)).as_str());
+        }
+    }
+    utils::log("");
+    utils::log("Use 'jump <number>' to go to the directory containing a file");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::filesystem_entry::excludes::Excludes;
+    use std::fs;
+
+    #[test]
+    fn test_find_duplicates() {
+        let dir = std::env::temp_dir().join(format!("qdirstat-dupes-{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub")).expect("create test directory");
+        let big : Vec<u8> = (0..20 * 1024).map(|idx| (idx % 251) as u8).collect();
+        let mut big_tail = big.clone();
+        big_tail[18 * 1024] ^= 1;
+        for (name, content) in [
+            ("a.bin", big.as_slice()),
+            ("sub/a copy.bin", big.as_slice()),
+            // Same head and size, different after the first 16 KiB
+            ("tail.bin", big_tail.as_slice()),
+            ("x.txt", b"hello".as_slice()),
+            ("y.txt", b"hello".as_slice()),
+            ("sub/z.txt", b"hello".as_slice()),
+            // Same size, different content
+            ("other.txt", b"world".as_slice()),
+            ("empty1", b"".as_slice()),
+            ("empty2", b"".as_slice()),
+        ] {
+            fs::write(dir.join(name), content).expect("write test file");
+        }
+        let root = FileSystemEntry::from_path(&dir, &Excludes::none()).expect("scan test directory");
+
+        let paths = |group: &DuplicateGroup| -> Vec<String> { group.files.iter().map(|file| file.relative_to(&root)).collect() };
+        let duplicates = find_duplicates(&root);
+        let mut results = Vec::<String>::new();
+        print_duplicates(&root, &mut results);
+        fs::remove_dir_all(&dir).expect("remove test directory");
+
+        assert_eq!(duplicates.len(), 2);
+        assert_eq!((duplicates[0].size, duplicates[0].wasted()), (20 * 1024, 20 * 1024));
+        assert_eq!(paths(&duplicates[0]), vec!["a.bin", "sub/a copy.bin"]);
+        assert_eq!((duplicates[1].size, duplicates[1].wasted()), (5, 10));
+        assert_eq!(paths(&duplicates[1]), vec!["sub/z.txt", "x.txt", "y.txt"]);
+
+        // Results are numbered in the printed order, and jump goes to the parent of each
+        assert_eq!(results.len(), 5);
+        let mut chain = root.ancestry(&results[1]).expect("listed file is in the tree");
+        chain.pop();
+        assert_eq!(chain.last().map(|entry| entry.identifier.as_str()), Some("sub"));
+    }
+}
@@ -0,0 +1,77 @@
+use super::filesystem_entry::{
+    FileSystemEntry,
+    filesystem_entry_type::FileSystemEntryType,
+};
+
+use crossterm::style::Color;
+
+/// Coarse grouping of files by extension, used to color graphical views
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FileCategory {
+    Directory,
+    Image,
+    Video,
+    Audio,
+    Archive,
+    Document,
+    Source,
+    Binary,
+    Other,
+}
+
+const IMAGE: [&str; 14] = ["jpg", "jpeg", "png", "gif", "bmp", "svg", "webp", "tif", "tiff", "ico", "heic", "raw", "cr2", "nef"];
+const VIDEO: [&str; 11] = ["mp4", "mkv", "avi", "mov", "wmv", "webm", "flv", "m4v", "mpg", "mpeg", "ts"];
+const AUDIO: [&str; 9] = ["mp3", "flac", "wav", "ogg", "m4a", "aac", "wma", "opus", "mid"];
+const ARCHIVE: [&str; 21] = ["zip", "tar", "tar.gz", "tgz", "tar.bz2", "tar.xz", "tar.zst", "tar.lz", "tar.lzma", "tar.z", "gz", "bz2", "xz", "zst", "7z", "rar", "iso", "dmg", "deb", "rpm", "cab"];
+const DOCUMENT: [&str; 14] = ["pdf", "doc", "docx", "odt", "xls", "xlsx", "ods", "ppt", "pptx", "txt", "md", "rtf", "epub", "csv"];
+const SOURCE: [&str; 22] = ["rs", "c", "h", "cpp", "hpp", "cc", "py", "js", "jsx", "tsx", "java", "go", "rb", "sh", "json", "toml", "yaml", "yml", "xml", "html", "css", "sql"];
+const BINARY: [&str; 13] = ["exe", "dll", "so", "a", "o", "lib", "bin", "dylib", "class", "jar", "pyc", "wasm", "msi"];
+
+pub fn category_of(entry: &FileSystemEntry) -> FileCategory {
+    if let FileSystemEntryType::Directory = entry.entry_type {
+        return FileCategory::Directory;
+    }
+
+    let extension = match entry.extension() {
+        Some(extension) => extension,
+        None => return FileCategory::Other,
+    };
+
+    // Shared libraries are usually versioned, e.g. libfoo.so.1.2
+    if entry.identifier.contains(".so.") {
+        return FileCategory::Binary;
+    }
+
+    let groups : [(&[&str], FileCategory); 7] = [
+        (&IMAGE, FileCategory::Image),
+        (&VIDEO, FileCategory::Video),
+        (&AUDIO, FileCategory::Audio),
+        (&ARCHIVE, FileCategory::Archive),
+        (&DOCUMENT, FileCategory::Document),
+        (&SOURCE, FileCategory::Source),
+        (&BINARY, FileCategory::Binary),
+    ];
+    for (extensions, category) in groups.iter() {
+        if extensions.contains(&extension.as_str()) {
+            return *category;
+        }
+    }
+
+    FileCategory::Other
+}
+
+impl FileCategory {
+    pub fn color(self) -> Color {
+        match self {
+            FileCategory::Directory => Color::Rgb { r: 110, g: 110, b: 110 },
+            FileCategory::Image => Color::Rgb { r: 60, g: 170, b: 220 },
+            FileCategory::Video => Color::Rgb { r: 170, g: 90, b: 210 },
+            FileCategory::Audio => Color::Rgb { r: 230, g: 200, b: 60 },
+            FileCategory::Archive => Color::Rgb { r: 220, g: 70, b: 70 },
+            FileCategory::Document => Color::Rgb { r: 80, g: 190, b: 90 },
+            FileCategory::Source => Color::Rgb { r: 240, g: 140, b: 50 },
+            FileCategory::Binary => Color::Rgb { r: 60, g: 90, b: 200 },
+            FileCategory::Other => Color::Rgb { r: 170, g: 170, b: 140 },
+        }
+    }
+}
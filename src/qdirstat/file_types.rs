@@ -0,0 +1,88 @@
+use super::filesystem_entry::{
+    FileSystemEntry,
+    filesystem_entry_type::FileSystemEntryType,
+    filesystem_entry_extensions::*,
+};
+
+use std::collections::HashMap;
+
+const NO_EXTENSION: &str = "(none)";
+
+#[derive(Default)]
+struct TypeUsage {
+    bytes: u64,
+    files: u64,
+}
+
+fn extension_key(entry: &FileSystemEntry) -> String {
+    entry.extension().unwrap_or_else(|| NO_EXTENSION.to_string())
+}
+
+/// Prints the files below fse grouped by extension, largest group first
+pub fn print_file_types(fse: &FileSystemEntry) {
+    let mut types = HashMap::<String, TypeUsage>::new();
+    let mut total_bytes : u64 = 0;
+
+    fse.walk(&mut |entry: &FileSystemEntry| {
+        if let FileSystemEntryType::File = entry.entry_type {
+            let usage = types.entry(extension_key(entry)).or_default();
+            usage.bytes += entry.len;
+            usage.files += 1;
+            total_bytes += entry.len;
+        }
+    });
+
+    utils::log("");
+    utils::log(format!("\tFile types: {} ({})", fse.path_string, total_bytes.bytes_to_readable()).as_str());
+    utils::log("");
+
+    if types.is_empty() {
+        utils::log("No files");
+        return;
+    }
+
+    let mut rows : Vec<(&String, &TypeUsage)> = types.iter().collect();
+    rows.sort_by_key(|row| std::cmp::Reverse(row.1.bytes));
+
+    utils::log_i(format!("   {:<12} {:>10} {:>8} {:>10} {:>6}", "Type", "Size", "Files", "Average", "%").as_str());
+    for (extension, usage) in rows {
+        let percent = if total_bytes == 0 { 0.0 } else { usage.bytes as f64 * 100.0 / total_bytes as f64 };
+        utils::log(format!("   {:<12} {:>10} {:>8} {:>10} {:>5.1}%",
+            extension,
+            usage.bytes.bytes_to_readable(),
+            usage.files,
+            (usage.bytes / usage.files).bytes_to_readable(),
+            percent).as_str());
+    }
+    utils::log("");
+    utils::log("Use 'types <extension> [count]' to list the largest files of a type");
+}
+
+/// Prints the largest files below fse with the given extension
+pub fn print_largest_of_type(fse: &FileSystemEntry, extension: &str, count: usize) -> bool {
+    let wanted = extension.trim_start_matches('.').to_lowercase();
+    let mut files = Vec::<&FileSystemEntry>::new();
+
+    fse.walk(&mut |entry: &FileSystemEntry| {
+        if let FileSystemEntryType::File = entry.entry_type {
+            if extension_key(entry) == wanted {
+                files.push(entry);
+            }
+        }
+    });
+
+    if files.is_empty() {
+        utils::log_w(format!("No files of type '{}'", wanted).as_str());
+        return false;
+    }
+
+    files.sort_by_key(|entry| std::cmp::Reverse(entry.len));
+
+    utils::log("");
+    utils::log(format!("\tLargest '{}' files: {} ({} of {})", wanted, fse.path_string, count.min(files.len()), files.len()).as_str());
+    utils::log("");
+    for entry in files.iter().take(count) {
+        utils::log(format!("   {:>10}  {}", entry.len.bytes_to_readable(), entry.relative_to(fse)).as_str());
+    }
+    true
+}
@@ -1,7 +1,13 @@
 pub mod filesystem_entry_type;
 pub mod filesystem_entry_extensions;
+pub mod sort_order;
+pub mod listing_columns;
+pub mod excludes;
 
 use filesystem_entry_type::FileSystemEntryType;
+use sort_order::SortOrder;
+use listing_columns::ListingColumns;
+use excludes::Excludes;
 use filesystem_entry_extensions::*;
 
 use std::fs;
@@ -14,6 +20,9 @@ pub struct FileSystemEntry {
     pub path_string: String,
     pub entry_type: FileSystemEntryType,
     pub len : u64,
+    pub uid : u32,
+    pub gid : u32,
+    pub modified : Option<std::time::SystemTime>,
     children: Vec::<FileSystemEntry>,
 }
 
@@ -24,6 +33,9 @@ impl FileSystemEntry {
             path_string: path.as_os_str().to_str().expect("Could convert from OsString").to_string(),
             entry_type: entry_type,
             len: size,
+            uid: 0,
+            gid: 0,
+            modified: None,
             children: Vec::<FileSystemEntry>::new()
         }
     }
@@ -33,7 +45,30 @@ impl FileSystemEntry {
         FileSystemEntry::new(&drive, std::path::Path::new(&drive), FileSystemEntryType::Directory, 0)
     }
 
-    pub fn scan(&mut self) {
+    /// Scans the file or directory at path and everything below it, without progress output
+    pub fn from_path(path: &std::path::Path, excludes: &Excludes) -> io::Result<FileSystemEntry> {
+        let metadata = fs::symlink_metadata(path)?;
+        let name = match path.file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => path.to_string_lossy().to_string(),
+        };
+        Ok(FileSystemEntry::from_metadata(&name, path, &metadata, excludes))
+    }
+
+    fn from_metadata(name: &str, path: &std::path::Path, metadata: &fs::Metadata, excludes: &Excludes) -> FileSystemEntry {
+        let entry_type = if metadata.is_dir() {FileSystemEntryType::Directory} else {FileSystemEntryType::File};
+        let mut entry = FileSystemEntry::new(name, path, entry_type, metadata.len());
+        let owner = owner_ids(metadata);
+        entry.uid = owner.0;
+        entry.gid = owner.1;
+        entry.modified = metadata.modified().ok();
+        entry.calculate_children(excludes);
+        // size() will iterate the children just aquired for file size
+        entry.len = entry.size();
+        entry
+    }
+
+    pub fn scan(&mut self, excludes: &Excludes) {
         let (tx, rx) = std::sync::mpsc::channel();
         let ticker_thread = std::thread::spawn(move||{
             loop {
@@ -47,7 +82,8 @@ impl FileSystemEntry {
         });
         print!("Starting scan...");
 
-        self.calculate_children();
+        self.calculate_children(excludes);
+        self.len = self.size();
 
         tx.send("thread cancel").expect("Failed to send thread cancel");
         utils::log_s("...scan completed.");
@@ -81,7 +117,137 @@ impl FileSystemEntry {
         }
     }
 
-    fn calculate_children(&mut self) {
+    /// Visits every entry below this one, depth first.
+    pub fn walk<'a, F: FnMut(&'a FileSystemEntry)>(&'a self, f: &mut F) {
+        if let Some(children) = self.children() {
+            for child in children {
+                f(child);
+                child.walk(f);
+            }
+        }
+    }
+
+    /// Most recent modification time of this entry or anything below it
+    pub fn newest_modified(&self) -> Option<std::time::SystemTime> {
+        let mut newest = self.modified;
+        self.walk(&mut |entry: &FileSystemEntry| {
+            if entry.modified > newest {
+                newest = entry.modified;
+            }
+        });
+        newest
+    }
+
+    /// Chain of entries leading from this entry down to the entry at path, both ends included
+    pub fn ancestry(&self, path: &str) -> Option<Vec<&FileSystemEntry>> {
+        let target = std::path::Path::new(path);
+        if !target.starts_with(&self.path_string) {
+            return None;
+        }
+
+        let mut chain = vec![self];
+        let mut current = self;
+        while current.path_string != path {
+            let next = current.children()?.iter()
+                .find(|child| target.starts_with(&child.path_string))?;
+            chain.push(next);
+            current = next;
+        }
+
+        Some(chain)
+    }
+
+    /// Detaches the entry at path from the tree below this one and subtracts its size from every
+    /// ancestor, so the sizes stay right without a rescan. Returns the detached entry.
+    pub fn remove(&mut self, path: &str) -> Option<FileSystemEntry> {
+        let target = std::path::Path::new(path);
+        let idx = self.children.iter().position(|child| target.starts_with(&child.path_string))?;
+        let removed = if self.children[idx].path_string == path {
+            self.children.remove(idx)
+        } else {
+            self.children[idx].remove(path)?
+        };
+        self.len = self.len.saturating_sub(removed.len);
+        Some(removed)
+    }
+
+    /// Adds entry to the tree below this one, replacing an entry with the same path, and
+    /// updates the size of every ancestor. Returns false when its parent is not in the tree.
+    pub fn insert(&mut self, entry: FileSystemEntry) -> bool {
+        let target = std::path::Path::new(&entry.path_string).to_path_buf();
+        if !matches!(self.entry_type, FileSystemEntryType::Directory) || !target.starts_with(&self.path_string) || self.path_string == entry.path_string {
+            return false;
+        }
+
+        if target.parent() == Some(std::path::Path::new(&self.path_string)) {
+            self.remove(&entry.path_string);
+            self.len += entry.len;
+            self.children.push(entry);
+            return true;
+        }
+
+        match self.children.iter_mut().find(|child| target.starts_with(&child.path_string)) {
+            None => false,
+            Some(child) => {
+                let child_len = child.len;
+                if !child.insert(entry) {
+                    return false;
+                }
+                self.len = (self.len + child.len).saturating_sub(child_len);
+                true
+            }
+        }
+    }
+
+    /// Scans path again and puts the result in place of what the tree held for it. An entry
+    /// that no longer exists is removed.
+    pub fn rescan(&mut self, path: &str, excludes: &Excludes) {
+        match FileSystemEntry::from_path(std::path::Path::new(path), excludes) {
+            Ok(entry) if entry.path_string == self.path_string => *self = entry,
+            Ok(entry) => {
+                self.insert(entry);
+            },
+            Err(_e) => {
+                self.remove(path);
+            },
+        }
+    }
+
+    /// A tree for tests, built from (path, type, size) of the entries below root. Parents must
+    /// come before their children.
+    #[cfg(test)]
+    pub fn from_paths(root: &str, entries: &[(&str, FileSystemEntryType, u64)]) -> FileSystemEntry {
+        let mut tree = FileSystemEntry::from_drive(root);
+        for (path, entry_type, size) in entries {
+            let path = std::path::Path::new(path);
+            let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            assert!(tree.insert(FileSystemEntry::new(&name, path, entry_type.clone(), *size)), "no parent for {}", path.display());
+        }
+        tree
+    }
+
+    /// Number of entries below this one
+    pub fn item_count(&self) -> u64 {
+        let mut count : u64 = 0;
+        self.walk(&mut |_entry: &FileSystemEntry| count += 1);
+        count
+    }
+
+    /// Lower-cased extension of a file, None for directories and files without one
+    pub fn extension(&self) -> Option<String> {
+        match self.entry_type {
+            FileSystemEntryType::File => file_extension(&self.identifier),
+            FileSystemEntryType::Directory => None,
+        }
+    }
+
+    /// Path of this entry relative to one of its ancestors
+    pub fn relative_to(&self, ancestor: &FileSystemEntry) -> String {
+        let relative = self.path_string.strip_prefix(ancestor.path_string.as_str()).unwrap_or(&self.path_string);
+        relative.trim_start_matches(std::path::MAIN_SEPARATOR).to_string()
+    }
+
+    fn calculate_children(&mut self, excludes: &Excludes) {
         match self.entry_type {
             FileSystemEntryType::File => {
 
@@ -104,25 +270,16 @@ impl FileSystemEntry {
                                 Ok(e) => {
                                     let entry : &fs::DirEntry = &e;
                                     let filename : String = String::from(entry.file_name().to_str().unwrap());
-                                    let entry_descriptor : FileSystemEntryType;
-                                    let mut size : u64 = 0;
-                                    match entry.metadata() {
+                                    if excludes.matches(&filename, &entry.path()) {
+                                        continue;
+                                    }
+                                    let new_entry = match entry.metadata() {
                                         Err(_e) => {
                                             utils::log_w("Failed to read metadata on file. Consider running as admin");
-                                            entry_descriptor = FileSystemEntryType::File;
+                                            FileSystemEntry::new(&filename, &entry.path().as_path(), FileSystemEntryType::File, 0)
                                         },
-                                        Ok(metadata) => {
-                                            entry_descriptor = if metadata.is_dir() {FileSystemEntryType::Directory} else {FileSystemEntryType::File};
-                                            size = metadata.len();
-                                        }
-                                    }
-                                    if filename.starts_with('$') || filename.eq("System Volume Information") || filename.starts_with('.'){
-                                        continue;
-                                    }
-                                    let mut new_entry = FileSystemEntry::new(&filename, &entry.path().as_path(), entry_descriptor, size);
-                                    new_entry.calculate_children();
-                                    // size() will iterate the children just aquired for file size
-                                    new_entry.len = new_entry.size();
+                                        Ok(metadata) => FileSystemEntry::from_metadata(&filename, &entry.path(), &metadata, excludes),
+                                    };
                                     directory_items.push(new_entry);
                                 }
                                 }
@@ -134,12 +291,8 @@ impl FileSystemEntry {
         }
     }
 
-    pub fn print(&self, visited_list: &Vec::<&String>) {
-        let mut children_view = Vec::<FileSystemEntryChildrenView>::new();  
-        for child in self.children().expect("I know you have a value") {
-            let c1 = child.clone();
-            children_view.push((c1.entry_type, c1.identifier, c1.len, c1.path_string));
-        }
+    pub fn print(&self, visited_list: &[String], order: &SortOrder) {
+        let mut children_view : Vec<&FileSystemEntry> = self.children().expect("I know you have a value").iter().collect();
 
         if children_view.len() == 0 {
             utils::log("No directories");
@@ -147,19 +300,50 @@ impl FileSystemEntry {
             utils::log("");
             utils::log(format!("\tDirectory: {}", self.path_string).as_str());
             utils::log("");
-            children_view.sort_by_key(|k|k.2);
+            order.sort(&mut children_view);
+            let columns = ListingColumns::for_terminal();
+            utils::log(columns.header().as_str());
             for view_entry in children_view.iter() {
-                if visited_list.contains(&&view_entry.3) {
-                    utils::log_s(format!(" {:?}  {} ({})", view_entry.0, view_entry.1, view_entry.2.bytes_to_readable()).as_str());
+                let row = columns.row(view_entry, self.len);
+                if visited_list.contains(&view_entry.path_string) {
+                    utils::log_s(row.as_str());
                 } else {
-                    utils::log(format!(" {:?}  {} ({})", view_entry.0, view_entry.1, view_entry.2.bytes_to_readable()).as_str());
+                    utils::log(row.as_str());
                 }
             }
         }
     }
 }
 
-pub type FileSystemEntryChildrenView = (FileSystemEntryType, String, u64, String);
+// Extensions that only make sense together, e.g. archive.tar.gz is a "tar.gz" and not a "gz"
+const COMPOUND_EXTENSIONS: [&str; 7] = ["tar.gz", "tar.bz2", "tar.xz", "tar.zst", "tar.lz", "tar.lzma", "tar.z"];
+
+pub fn file_extension(name: &str) -> Option<String> {
+    let lower = name.to_lowercase();
+    for compound in COMPOUND_EXTENSIONS.iter() {
+        if lower.len() > compound.len() + 1 && lower.ends_with(&format!(".{}", compound)) {
+            return Some(compound.to_string());
+        }
+    }
+
+    // A leading dot marks a hidden file rather than an extension
+    match lower.rfind('.') {
+        Some(idx) if idx > 0 && idx + 1 < lower.len() => Some(lower[idx + 1..].to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(unix)]
+fn owner_ids(metadata: &fs::Metadata) -> (u32, u32) {
+    use std::os::unix::fs::MetadataExt;
+    (metadata.uid(), metadata.gid())
+}
+
+// Windows has no numeric owners, everything is reported under id 0
+#[cfg(not(unix))]
+fn owner_ids(_metadata: &fs::Metadata) -> (u32, u32) {
+    (0, 0)
+}
 
 impl std::fmt::Display for FileSystemEntry {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -174,7 +358,76 @@ impl Clone for FileSystemEntry {
             path_string: self.path_string.clone(),
             entry_type: self.entry_type.clone(),
             len: self.len,
+            uid: self.uid,
+            gid: self.gid,
+            modified: self.modified,
             children: Vec::<FileSystemEntry>::new()
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_extension() {
+        assert_eq!(file_extension("Report.PDF"), Some(String::from("pdf")));
+        assert_eq!(file_extension("backup.2021.Tar.GZ"), Some(String::from("tar.gz")));
+        assert_eq!(file_extension("notes.gz"), Some(String::from("gz")));
+        assert_eq!(file_extension(".tar.gz"), Some(String::from("gz")));
+        assert_eq!(file_extension("Makefile"), None);
+        assert_eq!(file_extension(".bashrc"), None);
+        assert_eq!(file_extension("trailing."), None);
+    }
+
+    #[test]
+    fn test_scan_sets_root_size() {
+        let dir = std::env::temp_dir().join(format!("qdirstat-scan-{}", std::process::id()));
+        fs::create_dir_all(dir.join("logs")).expect("create test directory");
+        fs::write(dir.join("logs").join("a.log"), vec![0u8; 300]).expect("write test file");
+        fs::write(dir.join("b.txt"), vec![0u8; 100]).expect("write test file");
+
+        let mut root = FileSystemEntry::from_drive(dir.to_str().unwrap_or_default());
+        root.scan(&Excludes::none());
+        fs::remove_dir_all(&dir).expect("remove test directory");
+
+        assert_eq!(root.len, 400);
+        let columns = ListingColumns::for_width(120);
+        let logs = root.children.iter().find(|child| child.identifier == "logs").expect("logs was scanned");
+        assert!(columns.row(logs, root.len).contains("75.0%"));
+    }
+
+    #[test]
+    fn test_remove_and_insert_update_ancestor_sizes() {
+        let dir = |path: &str, children: Vec<FileSystemEntry>| {
+            let mut entry = FileSystemEntry::new(path, std::path::Path::new(path), FileSystemEntryType::Directory, 0);
+            entry.len = children.iter().map(|child| child.len).sum();
+            entry.children = children;
+            entry
+        };
+        let file = |path: &str, len: u64| FileSystemEntry::new(path, std::path::Path::new(path), FileSystemEntryType::File, len);
+
+        let mut root = dir("/data", vec![
+            dir("/data/logs", vec![file("/data/logs/a.log", 300), file("/data/logs/b.log", 200)]),
+            file("/data/logs.txt", 50),
+        ]);
+
+        let removed = root.remove("/data/logs/a.log").expect("entry exists");
+        assert_eq!(removed.len, 300);
+        assert_eq!(root.len, 250);
+        assert_eq!(root.children[0].len, 200);
+
+        assert!(root.remove("/data/logs/a.log").is_none());
+        assert!(root.remove("/data/log").is_none());
+        assert!(root.insert(file("/data/logs/c.log", 25)));
+        assert!(root.insert(file("/data/logs/c.log", 100)));
+        assert_eq!(root.len, 350);
+        assert_eq!(root.children[0].children.len(), 2);
+        assert!(!root.insert(file("/data/missing/d.log", 10)));
+        assert!(!root.insert(file("/elsewhere", 10)));
+
+        assert!(root.remove("/data/logs").is_some());
+        assert_eq!(root.len, 50);
+        assert_eq!(root.children.len(), 1);
+    }
+}
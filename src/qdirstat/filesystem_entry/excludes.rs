@@ -0,0 +1,66 @@
+/// Entries that scans skip. Patterns are globs on the entry name, or on the full path when
+/// they contain a path separator.
+#[derive(Clone, Debug)]
+pub struct Excludes {
+    patterns: Vec<glob::Pattern>,
+}
+
+impl Excludes {
+    // Hidden files and the Windows system folders
+    pub const DEFAULT: [&'static str; 3] = ["$*", "System Volume Information", ".*"];
+
+    pub fn new(patterns: &[String]) -> Result<Excludes, String> {
+        let mut excludes = Excludes::none();
+        for pattern in patterns {
+            let glob = glob::Pattern::new(pattern).map_err(|e| format!("Invalid exclude '{}': {}", pattern, e))?;
+            excludes.patterns.push(glob);
+        }
+        Ok(excludes)
+    }
+
+    /// Scans everything
+    pub fn none() -> Excludes {
+        Excludes { patterns: Vec::<glob::Pattern>::new() }
+    }
+
+    pub fn matches(&self, name: &str, path: &std::path::Path) -> bool {
+        self.patterns.iter().any(|pattern| {
+            if pattern.as_str().contains(std::path::MAIN_SEPARATOR) {
+                pattern.matches_path(path)
+            } else {
+                pattern.matches(name)
+            }
+        })
+    }
+
+    pub fn patterns(&self) -> Vec<&str> {
+        self.patterns.iter().map(|pattern| pattern.as_str()).collect()
+    }
+}
+
+impl Default for Excludes {
+    fn default() -> Excludes {
+        let defaults : Vec<String> = Excludes::DEFAULT.iter().map(|pattern| pattern.to_string()).collect();
+        Excludes::new(&defaults).expect("default excludes are valid globs")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_excludes() {
+        let defaults = Excludes::default();
+        assert!(defaults.matches(".git", Path::new("/src/.git")));
+        assert!(defaults.matches("$Recycle.Bin", Path::new("C:\\$Recycle.Bin")));
+        assert!(!defaults.matches("src", Path::new("/src")));
+
+        let custom = Excludes::new(&[String::from("node_modules"), String::from("/proc/*")]).expect("valid globs");
+        assert!(custom.matches("node_modules", Path::new("/web/node_modules")));
+        assert!(custom.matches("1", Path::new("/proc/1")));
+        assert!(!custom.matches("proc", Path::new("/proc")));
+        assert!(Excludes::new(&[String::from("[")]).is_err());
+    }
+}
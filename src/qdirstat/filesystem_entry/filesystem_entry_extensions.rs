@@ -6,6 +6,40 @@ enum ByteUnits {
     Gigabytes,
 }
 
+/// How sizes are displayed: powers of 1024 or powers of 1000
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Units {
+    Binary,
+    Decimal,
+}
+
+impl Units {
+    pub fn parse(name: &str) -> Result<Units, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "binary" | "1024" => Ok(Units::Binary),
+            "decimal" | "si" | "1000" => Ok(Units::Decimal),
+            _ => Err(format!("Invalid units '{}', expected binary or decimal", name)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Units::Binary => "binary",
+            Units::Decimal => "decimal",
+        }
+    }
+}
+
+static DECIMAL_UNITS: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+pub fn set_units(units: Units) {
+    DECIMAL_UNITS.store(units == Units::Decimal, std::sync::atomic::Ordering::Relaxed);
+}
+
+pub fn units() -> Units {
+    if DECIMAL_UNITS.load(std::sync::atomic::Ordering::Relaxed) { Units::Decimal } else { Units::Binary }
+}
+
 pub trait BytesExt {
     fn bytes_to_readable(self) -> String;
 }
@@ -33,7 +67,10 @@ pub fn bytes_to_readable(num_of_bytes: u64) -> String {
 
     val = get(ByteUnits::Kilobytes, num_of_bytes);
     if val > 0 {
-        readable = format!("{} KB", val).to_string();
+        readable = match units() {
+            Units::Binary => format!("{} KB", val),
+            Units::Decimal => format!("{} kB", val),
+        };
         return readable
     }
 
@@ -50,6 +87,66 @@ fn get(x: ByteUnits, num_of_bytes: u64) -> u64 {
 }
 
 fn divide(multiplier: u32, num_of_bytes: u64) -> u64 {
-    let res = u64::pow(1024, multiplier);
+    let base = match units() {
+        Units::Binary => 1024,
+        Units::Decimal => 1000,
+    };
+    let res = u64::pow(base, multiplier);
     num_of_bytes / res
 }
+
+/// Parses a human readable size such as "500M", "1.5GB" or "42" (bytes), the inverse of
+/// bytes_to_readable. Units are always powers of 1024 up to terabytes.
+pub fn readable_to_bytes(readable: &str) -> Option<u64> {
+    let trimmed = readable.trim();
+    let split = trimmed.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+    let number : f64 = number.parse().ok()?;
+
+    let unit = unit.trim().to_ascii_uppercase();
+    let multiplier = match unit.trim_end_matches("IB").trim_end_matches('B') {
+        "" => 0,
+        "K" => 1,
+        "M" => 2,
+        "G" => 3,
+        "T" => 4,
+        _ => return None,
+    };
+
+    Some((number * u64::pow(1024, multiplier) as f64) as u64)
+}
+
+pub fn format_date(time: Option<std::time::SystemTime>) -> String {
+    match time {
+        Some(time) => chrono::DateTime::<chrono::Local>::from(time).format("%Y-%m-%d").to_string(),
+        None => String::from("unknown"),
+    }
+}
+
+/// A bar of width cells filled in proportion to fraction
+pub fn bar(fraction: f64, width: usize) -> String {
+    let filled = ((fraction.clamp(0.0, 1.0) * width as f64).round() as usize).min(width);
+    format!("{}{}", "█".repeat(filled), "░".repeat(width - filled))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_readable_to_bytes() {
+        assert_eq!(readable_to_bytes("42"), Some(42));
+        assert_eq!(readable_to_bytes("500M"), Some(500 * 1024 * 1024));
+        assert_eq!(readable_to_bytes("1.5GB"), Some(1536 * 1024 * 1024));
+        assert_eq!(readable_to_bytes("2 KiB"), Some(2048));
+        assert_eq!(readable_to_bytes("12 parsecs"), None);
+        assert_eq!(readable_to_bytes("G"), None);
+    }
+
+    #[test]
+    fn test_parse_units() {
+        assert_eq!(Units::parse("Decimal"), Ok(Units::Decimal));
+        assert_eq!(Units::parse("1024"), Ok(Units::Binary));
+        assert!(Units::parse("metric").is_err());
+    }
+}
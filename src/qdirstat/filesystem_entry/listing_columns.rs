@@ -0,0 +1,122 @@
+use super::FileSystemEntry;
+use super::filesystem_entry_extensions::*;
+
+use console::{pad_str, Alignment};
+
+// Used when stdout is not a terminal, e.g. when the output is piped
+const DEFAULT_WIDTH: usize = 100;
+const MIN_NAME_WIDTH: usize = 16;
+const MAX_BAR_WIDTH: usize = 20;
+const MIN_BAR_WIDTH: usize = 5;
+
+// Type glyph, size and percent are always shown
+const GLYPH_WIDTH: usize = 3;
+const SIZE_WIDTH: usize = 10;
+const PERCENT_WIDTH: usize = 6;
+const ITEMS_WIDTH: usize = 8;
+const MODIFIED_WIDTH: usize = 10;
+
+/// Column layout of directory listings. Optional columns are dropped, least important first,
+/// when the terminal is too narrow for them.
+#[derive(Debug, PartialEq)]
+pub struct ListingColumns {
+    name_width: usize,
+    bar_width: usize,
+    show_items: bool,
+    show_modified: bool,
+}
+
+impl ListingColumns {
+    pub fn for_terminal() -> ListingColumns {
+        let width = crossterm::terminal::size().map(|(width, _height)| width as usize).unwrap_or(DEFAULT_WIDTH);
+        ListingColumns::for_width(width)
+    }
+
+    pub fn for_width(width: usize) -> ListingColumns {
+        let mut remaining = width.saturating_sub(GLYPH_WIDTH + SIZE_WIDTH + 1 + PERCENT_WIDTH + 1);
+
+        let show_items = remaining > MIN_NAME_WIDTH + ITEMS_WIDTH;
+        if show_items {
+            remaining -= ITEMS_WIDTH + 1;
+        }
+        let show_modified = remaining > MIN_NAME_WIDTH + MODIFIED_WIDTH;
+        if show_modified {
+            remaining -= MODIFIED_WIDTH + 1;
+        }
+
+        // The bar gets a third of whatever the name doesn't need
+        let bar_width = (remaining.saturating_sub(MIN_NAME_WIDTH + 1) / 3).min(MAX_BAR_WIDTH);
+        let bar_width = if bar_width < MIN_BAR_WIDTH { 0 } else { bar_width };
+        if bar_width > 0 {
+            remaining -= bar_width + 1;
+        }
+
+        ListingColumns { name_width: remaining.max(1), bar_width, show_items, show_modified }
+    }
+
+    // Cells are glyph, name, size, percent, bar, items and modified
+    fn line(&self, cells: [&str; 7]) -> String {
+        let [glyph, name, size, percent, bar, items, modified] = cells;
+        let mut line = format!(" {} {} {:>size_width$} {:>percent_width$}",
+            pad_str(glyph, GLYPH_WIDTH - 2, Alignment::Left, None),
+            pad_str(name, self.name_width, Alignment::Left, Some("…")),
+            size,
+            percent,
+            size_width = SIZE_WIDTH,
+            percent_width = PERCENT_WIDTH);
+        if self.bar_width > 0 {
+            line.push(' ');
+            line.push_str(&pad_str(bar, self.bar_width, Alignment::Left, None));
+        }
+        if self.show_items {
+            line.push_str(&format!(" {:>width$}", items, width = ITEMS_WIDTH));
+        }
+        if self.show_modified {
+            line.push_str(&format!(" {:>width$}", modified, width = MODIFIED_WIDTH));
+        }
+        line
+    }
+
+    pub fn header(&self) -> String {
+        self.line([" ", "Name", "Size", "%", "", "Items", "Modified"])
+    }
+
+    /// One listing row for entry, with its share of parent_len
+    pub fn row(&self, entry: &FileSystemEntry, parent_len: u64) -> String {
+        let fraction = if parent_len == 0 { 0.0 } else { entry.len as f64 / parent_len as f64 };
+        let items = match entry.children() {
+            Some(_) => entry.item_count().to_string(),
+            None => String::new(),
+        };
+        self.line([
+            &format!("{:?}", entry.entry_type),
+            &entry.identifier,
+            &entry.len.bytes_to_readable(),
+            &format!("{:.1}%", fraction * 100.0),
+            &bar(fraction, self.bar_width),
+            &items,
+            &format_date(entry.newest_modified()),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_columns_adapt_to_width() {
+        let wide = ListingColumns::for_width(120);
+        assert!(wide.show_items && wide.show_modified);
+        assert_eq!(wide.bar_width, MAX_BAR_WIDTH);
+        assert_eq!(console::measure_text_width(&wide.header()), 120);
+
+        let narrow = ListingColumns::for_width(50);
+        assert!(narrow.show_items && !narrow.show_modified);
+        assert_eq!(narrow.bar_width, 0);
+
+        let tiny = ListingColumns::for_width(20);
+        assert!(!tiny.show_items && !tiny.show_modified);
+        assert_eq!(tiny.name_width, 1);
+    }
+}
@@ -0,0 +1,119 @@
+use super::FileSystemEntry;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SortKey {
+    Size,
+    Name,
+    Items,
+    Modified,
+    Extension,
+    Path,
+}
+
+impl SortKey {
+    pub const ALL: [SortKey; 6] = [SortKey::Size, SortKey::Name, SortKey::Items, SortKey::Modified, SortKey::Extension, SortKey::Path];
+
+    pub fn parse(key: &str) -> Result<SortKey, String> {
+        match key.to_ascii_lowercase().as_str() {
+            "size" => Ok(SortKey::Size),
+            "name" => Ok(SortKey::Name),
+            "items" | "count" => Ok(SortKey::Items),
+            "mtime" | "age" | "date" => Ok(SortKey::Modified),
+            "ext" | "extension" | "type" => Ok(SortKey::Extension),
+            "path" => Ok(SortKey::Path),
+            _ => Err(format!("Invalid sort key '{}', expected size, name, items, mtime, ext or path", key)),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SortKey::Size => "size",
+            SortKey::Name => "name",
+            SortKey::Items => "items",
+            SortKey::Modified => "mtime",
+            SortKey::Extension => "ext",
+            SortKey::Path => "path",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SortOrder {
+    pub key: SortKey,
+    pub descending: bool,
+}
+
+impl Default for SortOrder {
+    fn default() -> SortOrder {
+        SortOrder::new(SortKey::Size)
+    }
+}
+
+impl SortOrder {
+    /// Sizes, counts and dates read best largest/newest first, names alphabetically
+    pub fn new(key: SortKey) -> SortOrder {
+        SortOrder {
+            key,
+            descending: matches!(key, SortKey::Size | SortKey::Items | SortKey::Modified),
+        }
+    }
+
+    /// Parses a key optionally followed by a direction, e.g. "name" or "mtime asc"
+    pub fn parse(args: &[String]) -> Result<SortOrder, String> {
+        let mut args = args.iter().filter(|arg| !arg.is_empty());
+        let mut order = match args.next() {
+            Some(key) => SortOrder::new(SortKey::parse(key)?),
+            None => return Err(String::from("Sort requires a key: size, name, items, mtime, ext or path")),
+        };
+
+        match args.next().map(|direction| direction.to_ascii_lowercase()) {
+            None => {},
+            Some(direction) if direction == "asc" || direction == "ascending" => order.descending = false,
+            Some(direction) if direction == "desc" || direction == "descending" => order.descending = true,
+            Some(direction) => return Err(format!("Invalid sort direction '{}', expected asc or desc", direction)),
+        }
+
+        Ok(order)
+    }
+
+    pub fn sort(&self, entries: &mut [&FileSystemEntry]) {
+        match self.key {
+            SortKey::Size => entries.sort_by_key(|entry| entry.len),
+            SortKey::Name => entries.sort_by_cached_key(|entry| entry.identifier.to_lowercase()),
+            SortKey::Items => entries.sort_by_cached_key(|entry| entry.item_count()),
+            SortKey::Modified => entries.sort_by_cached_key(|entry| entry.newest_modified()),
+            SortKey::Extension => entries.sort_by_cached_key(|entry| (entry.extension(), entry.identifier.to_lowercase())),
+            SortKey::Path => entries.sort_by(|a, b| a.path_string.cmp(&b.path_string)),
+        }
+
+        if self.descending {
+            entries.reverse();
+        }
+    }
+}
+
+impl std::fmt::Display for SortOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.key.name(), if self.descending { "desc" } else { "asc" })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split(' ').map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(SortOrder::parse(&args("size")), Ok(SortOrder { key: SortKey::Size, descending: true }));
+        assert_eq!(SortOrder::parse(&args("name")), Ok(SortOrder { key: SortKey::Name, descending: false }));
+        assert_eq!(SortOrder::parse(&args("mtime asc")), Ok(SortOrder { key: SortKey::Modified, descending: false }));
+        assert_eq!(SortOrder::parse(&args("EXT desc")), Ok(SortOrder { key: SortKey::Extension, descending: true }));
+        assert!(SortOrder::parse(&args("colour")).is_err());
+        assert!(SortOrder::parse(&args("size sideways")).is_err());
+        assert!(SortOrder::parse(&[]).is_err());
+    }
+}
@@ -0,0 +1,285 @@
+use super::filesystem_entry::{
+    FileSystemEntry,
+    filesystem_entry_type::FileSystemEntryType,
+    filesystem_entry_extensions::*,
+    sort_order::{SortKey, SortOrder},
+};
+
+use chrono::{Local, NaiveDate, TimeZone};
+use std::time::{Duration, SystemTime};
+
+const DEFAULT_LIMIT: usize = 100;
+
+pub const OPTIONS: [&str; 9] = ["-name", "-regex", "-size", "-mtime", "-type", "-sort", "-asc", "-desc", "-limit"];
+
+pub const USAGE: &str = "find [glob] [-name glob] [-regex re] [-size +500M|-1G|10M..2G] [-mtime -7d|+1y|2023-01-01..2024-01-01] [-type f|d] [-sort size|name|items|mtime|ext|path] [-asc|-desc] [-limit n]";
+
+enum NamePredicate {
+    Glob(glob::Pattern),
+    Regex(regex::Regex),
+}
+
+pub struct FindQuery {
+    names: Vec<NamePredicate>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    modified_after: Option<SystemTime>,
+    modified_before: Option<SystemTime>,
+    entry_type: Option<FileSystemEntryType>,
+    order: SortOrder,
+    limit: usize,
+}
+
+// "+500M" is at least 500M, "-1G" is below 1G, "10M..2G" is the range in between, "5K" is exactly 5K
+fn parse_size_range(spec: &str) -> Result<(Option<u64>, Option<u64>), String> {
+    let size = |s: &str| readable_to_bytes(s).ok_or(format!("Invalid size '{}'", s));
+
+    if let Some((low, high)) = spec.split_once("..") {
+        let low = if low.is_empty() { None } else { Some(size(low)?) };
+        let high = if high.is_empty() { None } else { Some(size(high)?) };
+        return Ok((low, high));
+    }
+
+    match spec.chars().next() {
+        Some('+') => Ok((Some(size(&spec[1..])?), None)),
+        Some('-') => Ok((None, Some(size(&spec[1..])?.saturating_sub(1)))),
+        _ => {
+            let exact = size(spec)?;
+            Ok((Some(exact), Some(exact)))
+        }
+    }
+}
+
+// Relative ages such as "7d", "3w", "6m" or "1y" and calendar dates such as "2023-01-31"
+fn parse_time(spec: &str, now: SystemTime) -> Result<SystemTime, String> {
+    if let Ok(date) = NaiveDate::parse_from_str(spec, "%Y-%m-%d") {
+        let midnight = date.and_hms_opt(0, 0, 0).ok_or(format!("Invalid date '{}'", spec))?;
+        return match Local.from_local_datetime(&midnight).earliest() {
+            Some(time) => Ok(SystemTime::from(time)),
+            None => Err(format!("Invalid date '{}'", spec)),
+        };
+    }
+
+    let split = spec.find(|c: char| !c.is_ascii_digit()).unwrap_or(spec.len());
+    let (number, unit) = spec.split_at(split);
+    let number : u64 = number.parse().map_err(|_e| format!("Invalid age '{}'", spec))?;
+    let seconds = match unit {
+        "h" => 60 * 60,
+        "d" | "" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        "m" => 30 * 24 * 60 * 60,
+        "y" => 365 * 24 * 60 * 60,
+        _ => return Err(format!("Invalid age unit in '{}', expected h, d, w, m or y", spec)),
+    };
+
+    Ok(now.checked_sub(Duration::from_secs(number * seconds)).unwrap_or(SystemTime::UNIX_EPOCH))
+}
+
+// "-7d" was modified within the last 7 days, "+1y" more than a year ago, "2023-01-01..2024-01-01" in between
+fn parse_time_range(spec: &str, now: SystemTime) -> Result<(Option<SystemTime>, Option<SystemTime>), String> {
+    if let Some((after, before)) = spec.split_once("..") {
+        let after = if after.is_empty() { None } else { Some(parse_time(after, now)?) };
+        let before = if before.is_empty() { None } else { Some(parse_time(before, now)?) };
+        return Ok((after, before));
+    }
+
+    match spec.chars().next() {
+        Some('-') => Ok((Some(parse_time(&spec[1..], now)?), None)),
+        Some('+') => Ok((None, Some(parse_time(&spec[1..], now)?))),
+        _ => Err(format!("Invalid modification time '{}', expected e.g. -7d, +1y or 2023-01-01..2024-01-01", spec)),
+    }
+}
+
+fn glob_predicate(pattern: &str) -> Result<NamePredicate, String> {
+    match glob::Pattern::new(pattern) {
+        Ok(glob) => Ok(NamePredicate::Glob(glob)),
+        Err(e) => Err(format!("Invalid glob '{}': {}", pattern, e)),
+    }
+}
+
+impl FindQuery {
+    pub fn parse(args: &[String]) -> Result<FindQuery, String> {
+        let mut query = FindQuery {
+            names: Vec::<NamePredicate>::new(),
+            min_size: None,
+            max_size: None,
+            modified_after: None,
+            modified_before: None,
+            entry_type: None,
+            order: SortOrder::default(),
+            limit: DEFAULT_LIMIT,
+        };
+        let now = SystemTime::now();
+        let mut sort_key = SortKey::Size;
+        let mut explicit_order : Option<bool> = None;
+
+        let mut args = args.iter().filter(|arg| !arg.is_empty());
+        while let Some(arg) = args.next() {
+            let mut value = |option: &str| args.next().ok_or(format!("Option {} requires a value", option));
+            match arg.as_str() {
+                "-name" => query.names.push(glob_predicate(value(arg)?)?),
+                "-regex" => {
+                    let pattern = value(arg)?;
+                    let regex = regex::RegexBuilder::new(pattern)
+                        .case_insensitive(true)
+                        .build()
+                        .map_err(|e| format!("Invalid regex '{}': {}", pattern, e))?;
+                    query.names.push(NamePredicate::Regex(regex));
+                },
+                "-size" => {
+                    let (min, max) = parse_size_range(value(arg)?)?;
+                    query.min_size = min;
+                    query.max_size = max;
+                },
+                "-mtime" => {
+                    let (after, before) = parse_time_range(value(arg)?, now)?;
+                    query.modified_after = after;
+                    query.modified_before = before;
+                },
+                "-type" => {
+                    query.entry_type = match value(arg)?.as_str() {
+                        "f" | "file" => Some(FileSystemEntryType::File),
+                        "d" | "dir" | "directory" => Some(FileSystemEntryType::Directory),
+                        other => return Err(format!("Invalid type '{}', expected f or d", other)),
+                    };
+                },
+                "-sort" => sort_key = SortKey::parse(value(arg)?)?,
+                "-asc" => explicit_order = Some(false),
+                "-desc" => explicit_order = Some(true),
+                "-limit" => {
+                    let limit = value(arg)?;
+                    query.limit = limit.parse().map_err(|_e| format!("Invalid limit '{}'", limit))?;
+                },
+                option if option.starts_with('-') => return Err(format!("Unknown option '{}'", option)),
+                pattern => query.names.push(glob_predicate(pattern)?),
+            }
+        }
+
+        query.order = SortOrder::new(sort_key);
+        if let Some(descending) = explicit_order {
+            query.order.descending = descending;
+        }
+        Ok(query)
+    }
+
+    pub fn matches(&self, entry: &FileSystemEntry) -> bool {
+        let options = glob::MatchOptions {
+            case_sensitive: false,
+            ..glob::MatchOptions::new()
+        };
+        let name_matches = self.names.iter().all(|name| match name {
+            NamePredicate::Glob(glob) => glob.matches_with(&entry.identifier, options),
+            NamePredicate::Regex(regex) => regex.is_match(&entry.identifier),
+        });
+
+        let type_matches = match self.entry_type {
+            None => true,
+            Some(FileSystemEntryType::File) => matches!(entry.entry_type, FileSystemEntryType::File),
+            Some(FileSystemEntryType::Directory) => matches!(entry.entry_type, FileSystemEntryType::Directory),
+        };
+
+        let modified_matches = match entry.modified {
+            Some(modified) => self.modified_after.is_none_or(|after| modified >= after)
+                && self.modified_before.is_none_or(|before| modified < before),
+            None => self.modified_after.is_none() && self.modified_before.is_none(),
+        };
+
+        name_matches
+            && type_matches
+            && modified_matches
+            && self.min_size.is_none_or(|min| entry.len >= min)
+            && self.max_size.is_none_or(|max| entry.len <= max)
+    }
+
+    /// All entries below fse matching the query, in the requested order
+    pub fn run<'a>(&self, fse: &'a FileSystemEntry) -> Vec<&'a FileSystemEntry> {
+        let mut found = Vec::<&FileSystemEntry>::new();
+        fse.walk(&mut |entry: &'a FileSystemEntry| {
+            if self.matches(entry) {
+                found.push(entry);
+            }
+        });
+
+        self.order.sort(&mut found);
+        found
+    }
+}
+
+/// Prints the entries below fse matching the find arguments. results is refilled with their
+/// paths so they can be jumped to afterwards. False when the arguments are invalid.
+pub fn print_find(fse: &FileSystemEntry, args: &[String], results: &mut Vec<String>) -> bool {
+    let query = match FindQuery::parse(args) {
+        Ok(query) => query,
+        Err(e) => {
+            utils::log_w(e.as_str());
+            utils::log(format!("Usage: {}", USAGE).as_str());
+            return false;
+        }
+    };
+
+    results.clear();
+    let found = query.run(fse);
+
+    utils::log("");
+    utils::log(format!("\tFound: {} ({} entries)", fse.path_string, found.len()).as_str());
+    utils::log("");
+
+    if found.is_empty() {
+        utils::log("No matching entries");
+        return true;
+    }
+
+    for entry in found.iter().take(query.limit) {
+        results.push(entry.path_string.to_string());
+        let number = format!("[{}]", results.len());
+        utils::log(format!("   {:>5} {:>10}  {}  {:?}  {}", number, entry.len.bytes_to_readable(), format_date(entry.modified), entry.entry_type, entry.relative_to(fse)).as_str());
+    }
+    if found.len() > query.limit {
+        utils::log(format!("   ... {} more, use -limit to show them", found.len() - query.limit).as_str());
+    }
+    utils::log("");
+    utils::log("Use 'jump <number>' to go to the directory containing an entry");
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split(' ').map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_size_range() {
+        assert_eq!(parse_size_range("+500M"), Ok((Some(500 * 1024 * 1024), None)));
+        assert_eq!(parse_size_range("-1K"), Ok((None, Some(1023))));
+        assert_eq!(parse_size_range("1K..2K"), Ok((Some(1024), Some(2048))));
+        assert_eq!(parse_size_range("..2K"), Ok((None, Some(2048))));
+        assert!(parse_size_range("+lots").is_err());
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let query = FindQuery::parse(&args("*.iso -size +1G -type f -sort name")).expect("valid query");
+        assert_eq!(query.names.len(), 1);
+        assert_eq!(query.min_size, Some(1024 * 1024 * 1024));
+        assert_eq!(query.order, SortOrder { key: SortKey::Name, descending: false });
+
+        assert!(FindQuery::parse(&args("-size")).is_err());
+        assert!(FindQuery::parse(&args("-colour red")).is_err());
+        assert!(FindQuery::parse(&args("-mtime 7d")).is_err());
+        assert!(FindQuery::parse(&args("-mtime -7d -desc")).expect("valid query").order.descending);
+    }
+
+    #[test]
+    fn test_matches() {
+        let mut entry = FileSystemEntry::new("Backup.ISO", std::path::Path::new("/data/Backup.ISO"), FileSystemEntryType::File, 2048);
+        entry.modified = Some(SystemTime::now());
+
+        assert!(FindQuery::parse(&args("*.iso")).expect("valid query").matches(&entry));
+        assert!(FindQuery::parse(&args("-regex ^back -size 1K..4K -mtime -1d")).expect("valid query").matches(&entry));
+        assert!(!FindQuery::parse(&args("-type d")).expect("valid query").matches(&entry));
+        assert!(!FindQuery::parse(&args("-mtime +1d")).expect("valid query").matches(&entry));
+    }
+}
@@ -0,0 +1,75 @@
+use super::filesystem_entry::{
+    FileSystemEntry,
+    filesystem_entry_type::FileSystemEntryType,
+    filesystem_entry_extensions::*,
+};
+
+pub const DEFAULT_COUNT: usize = 20;
+
+/// The count largest files anywhere below fse, largest first
+pub fn largest_files(fse: &FileSystemEntry, count: usize) -> Vec<&FileSystemEntry> {
+    let mut files = Vec::<&FileSystemEntry>::new();
+    fse.walk(&mut |entry: &FileSystemEntry| {
+        if let FileSystemEntryType::File = entry.entry_type {
+            files.push(entry);
+        }
+    });
+
+    files.sort_by_key(|entry| std::cmp::Reverse(entry.len));
+    files.truncate(count);
+    files
+}
+
+/// Prints the count largest files below fse with their relative paths. results is refilled
+/// with their paths so they can be jumped to afterwards.
+pub fn print_largest_files(fse: &FileSystemEntry, count: usize, results: &mut Vec<String>) {
+    results.clear();
+    let files = largest_files(fse, count);
+
+    utils::log("");
+    utils::log(format!("\tLargest files: {}", fse.path_string).as_str());
+    utils::log("");
+
+    if files.is_empty() {
+        utils::log("No files");
+        return;
+    }
+
+    for file in files {
+        results.push(file.path_string.to_string());
+        let number = format!("[{}]", results.len());
+        utils::log(format!("   {:>5} {:>10}  {}", number, file.len.bytes_to_readable(), file.relative_to(fse)).as_str());
+    }
+    utils::log("");
+    utils::log("Use 'jump <number>' to go to the directory containing a file");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_largest_files() {
+        let root = FileSystemEntry::from_paths("/r", &[
+            ("/r/a", FileSystemEntryType::Directory, 0),
+            ("/r/a/b", FileSystemEntryType::Directory, 0),
+            ("/r/a/b/big.iso", FileSystemEntryType::File, 900),
+            ("/r/a/mid.log", FileSystemEntryType::File, 500),
+            ("/r/small.txt", FileSystemEntryType::File, 10),
+            ("/r/empty", FileSystemEntryType::Directory, 0),
+        ]);
+
+        let paths = |fse: &FileSystemEntry, count: usize| -> Vec<String> {
+            largest_files(fse, count).iter().map(|file| file.relative_to(fse)).collect()
+        };
+        assert_eq!(paths(&root, 2), vec!["a/b/big.iso", "a/mid.log"]);
+        assert_eq!(paths(&root, DEFAULT_COUNT).len(), 3);
+
+        let a = root.ancestry("/r/a").and_then(|chain| chain.last().copied()).expect("entry exists");
+        assert_eq!(paths(a, DEFAULT_COUNT), vec!["b/big.iso", "mid.log"]);
+
+        let mut results = Vec::<String>::new();
+        print_largest_files(a, 1, &mut results);
+        assert_eq!(results, vec!["/r/a/b/big.iso"]);
+    }
+}
@@ -0,0 +1,312 @@
+use console::Key;
+
+const CTRL_D: char = '\x04';
+const CTRL_G: char = '\x07';
+const CTRL_R: char = '\x12';
+const CTRL_U: char = '\x15';
+const CTRL_W: char = '\x17';
+
+// State of a Ctrl-R reverse incremental search
+struct Search {
+    query: String,
+    // Index into the history of the entry matching query
+    found: Option<usize>,
+    // The line before the search started, restored when it is cancelled
+    original: String,
+}
+
+/// The command line being typed at the terminal, with cursor movement and recall of earlier
+/// commands
+#[derive(Default)]
+pub struct LineEditor {
+    line: String,
+    // Byte offset of the cursor in line, always on a char boundary
+    cursor: usize,
+    // Index into the history of the entry recalled with Up/Down, None while on the new line
+    recalled: Option<usize>,
+    // What was typed before Up was first pressed, brought back by Down
+    draft: String,
+    search: Option<Search>,
+}
+
+// Newest entry at or before start that contains query
+fn find_older(history: &[String], query: &str, start: Option<usize>) -> Option<usize> {
+    let start = start?;
+    history[..=start.min(history.len().checked_sub(1)?)].iter().rposition(|entry| entry.contains(query))
+}
+
+// Combining marks take no space of their own, the cursor stays on the char they belong to
+fn starts_cluster(c: char) -> bool {
+    console::measure_text_width(c.encode_utf8(&mut [0; 4])) > 0
+}
+
+// Whitespace, then the word before it, as Ctrl-W deletes in a shell
+fn word_start(text: &str) -> usize {
+    let trimmed = text.trim_end();
+    trimmed.rfind(char::is_whitespace).map(|idx| idx + trimmed[idx..].chars().next().map_or(1, char::len_utf8)).unwrap_or(0)
+}
+
+impl LineEditor {
+    pub fn is_searching(&self) -> bool {
+        self.search.is_some()
+    }
+
+    pub fn line(&self) -> &str {
+        &self.line
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Replaces the text from start up to the cursor, leaving the cursor after the new text
+    pub fn replace_before_cursor(&mut self, start: usize, text: &str) {
+        self.line.replace_range(start..self.cursor, text);
+        self.cursor = start + text.len();
+    }
+
+    /// Replaces the line and puts the cursor at its end
+    pub fn set_line(&mut self, line: String) {
+        self.cursor = line.len();
+        self.line = line;
+    }
+
+    fn previous_boundary(&self) -> usize {
+        let mut chars = self.line[..self.cursor].char_indices().rev();
+        for (idx, c) in chars.by_ref() {
+            if starts_cluster(c) {
+                return idx;
+            }
+        }
+        0
+    }
+
+    fn next_boundary(&self) -> usize {
+        let mut chars = self.line[self.cursor..].char_indices().skip(1);
+        match chars.find(|(_idx, c)| starts_cluster(*c)) {
+            Some((idx, _c)) => self.cursor + idx,
+            None => self.line.len(),
+        }
+    }
+
+    /// Applies a key. Returns true when the line is complete and should run.
+    pub fn handle(&mut self, key: Key, history: &[String]) -> bool {
+        if self.search.is_some() {
+            return self.handle_search(key, history);
+        }
+
+        match key {
+            Key::Enter => return true,
+            Key::Backspace => {
+                let start = self.previous_boundary();
+                self.line.replace_range(start..self.cursor, "");
+                self.cursor = start;
+            },
+            Key::Del | Key::Char(CTRL_D) => {
+                let end = self.next_boundary();
+                self.line.replace_range(self.cursor..end, "");
+            },
+            Key::ArrowLeft => self.cursor = self.previous_boundary(),
+            Key::ArrowRight => self.cursor = self.next_boundary(),
+            Key::Home => self.cursor = 0,
+            Key::End => self.cursor = self.line.len(),
+            Key::Char(CTRL_W) => {
+                let start = word_start(&self.line[..self.cursor]);
+                self.line.replace_range(start..self.cursor, "");
+                self.cursor = start;
+            },
+            Key::Char(CTRL_U) => {
+                self.line.replace_range(..self.cursor, "");
+                self.cursor = 0;
+            },
+            Key::ArrowUp => {
+                let older = match self.recalled {
+                    None => history.len().checked_sub(1),
+                    Some(idx) => idx.checked_sub(1),
+                };
+                if let Some(idx) = older {
+                    if self.recalled.is_none() {
+                        self.draft = self.line.to_string();
+                    }
+                    self.recalled = Some(idx);
+                    self.set_line(history[idx].to_string());
+                }
+            },
+            Key::ArrowDown => {
+                if let Some(idx) = self.recalled {
+                    if idx + 1 < history.len() {
+                        self.recalled = Some(idx + 1);
+                        self.set_line(history[idx + 1].to_string());
+                    } else {
+                        self.recalled = None;
+                        let draft = std::mem::take(&mut self.draft);
+                        self.set_line(draft);
+                    }
+                }
+            },
+            Key::Char(CTRL_R) => {
+                self.search = Some(Search { query: String::new(), found: None, original: self.line.to_string() });
+            },
+            Key::Char(c) if !c.is_control() => {
+                self.line.insert(self.cursor, c);
+                self.cursor += c.len_utf8();
+            },
+            _ => {},
+        }
+        false
+    }
+
+    fn handle_search(&mut self, key: Key, history: &[String]) -> bool {
+        let search = match self.search.as_mut() {
+            Some(search) => search,
+            None => return false,
+        };
+        let newest = history.len().checked_sub(1);
+        match key {
+            Key::Char(CTRL_R) => {
+                // The next older match, or the newest one when nothing is found yet
+                let start = match search.found {
+                    Some(found) => found.checked_sub(1),
+                    None => newest,
+                };
+                if let Some(found) = find_older(history, &search.query, start) {
+                    search.found = Some(found);
+                }
+            },
+            Key::Char(c) if !c.is_control() => {
+                search.query.push(c);
+                search.found = find_older(history, &search.query, search.found.or(newest));
+            },
+            Key::Backspace => {
+                search.query.pop();
+                search.found = find_older(history, &search.query, newest);
+            },
+            Key::Escape | Key::Char(CTRL_G) => {
+                let original = std::mem::take(&mut search.original);
+                self.set_line(original);
+                self.search = None;
+            },
+            key => {
+                // Any other key takes the match onto the line and then does what it always does,
+                // so Enter runs it and e.g. Left starts editing it
+                if let Some(found) = search.found {
+                    self.recalled = Some(found);
+                    self.set_line(history[found].to_string());
+                }
+                self.search = None;
+                return self.handle(key, history);
+            },
+        }
+        false
+    }
+
+    /// The text to show for the line, which during a search is the query and its match
+    pub fn render(&self, history: &[String]) -> String {
+        match &self.search {
+            None => self.line.to_string(),
+            Some(search) => {
+                let found = search.found.map(|found| history[found].as_str()).unwrap_or_default();
+                let failed = if search.found.is_none() && !search.query.is_empty() { "failed " } else { "" };
+                format!("({}reverse-i-search)`{}': {}", failed, search.query, found)
+            }
+        }
+    }
+
+    /// How many columns left of the end of render the cursor goes
+    pub fn cursor_offset(&self) -> usize {
+        match &self.search {
+            None => console::measure_text_width(&self.line[self.cursor..]),
+            Some(_search) => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history() -> Vec<String> {
+        vec![String::from("cd tmp"), String::from("top 5"), String::from("cd lt")]
+    }
+
+    fn type_keys(editor: &mut LineEditor, keys: Vec<Key>) -> bool {
+        keys.into_iter().fold(false, |_submitted, key| editor.handle(key, &history()))
+    }
+
+    #[test]
+    fn test_recall() {
+        let mut editor = LineEditor::default();
+        type_keys(&mut editor, vec![Key::Char('l'), Key::ArrowUp, Key::ArrowUp]);
+        assert_eq!(editor.line(), "top 5");
+        type_keys(&mut editor, vec![Key::ArrowUp, Key::ArrowUp]);
+        assert_eq!(editor.line(), "cd tmp");
+        type_keys(&mut editor, vec![Key::ArrowDown, Key::ArrowDown, Key::ArrowDown]);
+        assert_eq!(editor.line(), "l");
+    }
+
+    #[test]
+    fn test_search() {
+        let mut editor = LineEditor::default();
+        type_keys(&mut editor, vec![Key::Char(CTRL_R), Key::Char('c'), Key::Char('d')]);
+        assert_eq!(editor.render(&history()), "(reverse-i-search)`cd': cd lt");
+        type_keys(&mut editor, vec![Key::Char(CTRL_R)]);
+        assert_eq!(editor.render(&history()), "(reverse-i-search)`cd': cd tmp");
+        type_keys(&mut editor, vec![Key::Char('x')]);
+        assert!(editor.render(&history()).starts_with("(failed reverse-i-search)"));
+        // Shortening the query starts over from the newest command
+        assert!(type_keys(&mut editor, vec![Key::Backspace, Key::Enter]));
+        assert_eq!(editor.line(), "cd lt");
+
+        let mut editor = LineEditor::default();
+        type_keys(&mut editor, vec![Key::Char('l'), Key::Char(CTRL_R), Key::Char('t'), Key::Escape]);
+        assert!(!editor.is_searching());
+        assert_eq!(editor.line(), "l");
+
+        // Keys that end the search still take effect on the match
+        let mut editor = LineEditor::default();
+        assert!(!type_keys(&mut editor, vec![Key::Char(CTRL_R), Key::Char('t'), Key::Char('o'), Key::ArrowLeft, Key::Char('p')]));
+        assert!(!editor.is_searching());
+        assert_eq!(editor.line(), "top p5");
+        type_keys(&mut editor, vec![Key::Char(CTRL_R), Key::Char('c'), Key::Char(CTRL_W)]);
+        assert_eq!(editor.line(), "cd ");
+    }
+
+    fn typed(text: &str) -> LineEditor {
+        let mut editor = LineEditor::default();
+        type_keys(&mut editor, text.chars().map(Key::Char).collect());
+        editor
+    }
+
+    #[test]
+    fn test_editing() {
+        let mut editor = typed("cd Prgram");
+        type_keys(&mut editor, vec![Key::ArrowLeft, Key::ArrowLeft, Key::ArrowLeft, Key::ArrowLeft, Key::Char('o')]);
+        assert_eq!(editor.line(), "cd Program");
+        type_keys(&mut editor, vec![Key::Home, Key::Del, Key::Del, Key::Char('l'), Key::Char('s'), Key::End, Key::Char('s')]);
+        assert_eq!(editor.line(), "ls Programs");
+        assert_eq!(editor.cursor_offset(), 0);
+
+        let mut editor = typed("find  *.iso  -size");
+        type_keys(&mut editor, vec![Key::Char(CTRL_W)]);
+        assert_eq!(editor.line(), "find  *.iso  ");
+        type_keys(&mut editor, vec![Key::Char(CTRL_W)]);
+        assert_eq!(editor.line(), "find  ");
+        type_keys(&mut editor, vec![Key::Char('x'), Key::ArrowLeft, Key::Char(CTRL_U)]);
+        assert_eq!((editor.line(), editor.cursor), ("x", 0));
+    }
+
+    #[test]
+    fn test_unicode() {
+        // A wide char, and an e followed by a combining acute accent
+        let mut editor = typed("cd 日本e\u{301}x");
+        type_keys(&mut editor, vec![Key::ArrowLeft, Key::ArrowLeft]);
+        assert_eq!(editor.cursor_offset(), 2);
+        type_keys(&mut editor, vec![Key::Backspace]);
+        assert_eq!(editor.line(), "cd 日e\u{301}x");
+        assert_eq!(editor.cursor_offset(), 2);
+        type_keys(&mut editor, vec![Key::Del, Key::Char('ü')]);
+        assert_eq!(editor.line(), "cd 日üx");
+        type_keys(&mut editor, vec![Key::End, Key::Backspace, Key::Backspace, Key::Backspace]);
+        assert_eq!(editor.line(), "cd ");
+    }
+}
@@ -0,0 +1,225 @@
+use super::filesystem_entry::{
+    FileSystemEntry,
+    filesystem_entry_type::FileSystemEntryType,
+};
+
+use std::path::{Component, Path, PathBuf};
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE")).map(PathBuf::from)
+}
+
+/// Resolves the target of cd against the scanned tree and returns the chain of entries from root
+/// down to the new directory. Targets are relative to location, or absolute, /, ~ or ~/path, or
+/// - for previous.
+pub fn resolve<'a>(root: &'a FileSystemEntry, location: &str, target: &str, previous: Option<&str>) -> Result<Vec<&'a FileSystemEntry>, String> {
+    if target == "-" {
+        let previous = previous.ok_or("No previous directory")?;
+        return root.ancestry(previous).ok_or(format!("{} is no longer in the tree", previous));
+    }
+
+    let expanded = match target.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', std::path::MAIN_SEPARATOR]) => {
+            let home = home_dir().ok_or("HOME is not set")?;
+            home.join(rest.trim_start_matches(['/', std::path::MAIN_SEPARATOR]))
+        },
+        _ => PathBuf::from(target),
+    };
+
+    let (mut chain, relative) = if expanded.has_root() {
+        let relative = expanded.strip_prefix(&root.path_string)
+            .map_err(|_e| format!("{} is outside the scanned tree {}", expanded.display(), root.path_string))?;
+        (vec![root], relative.to_path_buf())
+    } else {
+        (root.ancestry(location).unwrap_or_else(|| vec![root]), expanded)
+    };
+
+    for component in Path::new(&relative).components() {
+        match component {
+            Component::CurDir | Component::RootDir | Component::Prefix(_) => {},
+            Component::ParentDir => {
+                if chain.len() < 2 {
+                    return Err(String::from("No parent directory exists"));
+                }
+                chain.pop();
+            },
+            Component::Normal(name) => {
+                let name = name.to_string_lossy();
+                let current = chain.last().copied().unwrap_or(root);
+                let entry = super::child_named(current, &name).ok_or(format!("No entry matches target '{}'", name))?;
+                if let FileSystemEntryType::File = entry.entry_type {
+                    return Err(format!("Change directory target {} is a file.", entry.path_string));
+                }
+                chain.push(entry);
+            },
+        }
+    }
+    Ok(chain)
+}
+
+/// Browser style history of the directories moved through
+#[derive(Default)]
+pub struct History {
+    back: Vec<String>,
+    forward: Vec<String>,
+    // Most recent first, without repeats
+    recent: Vec<String>,
+}
+
+impl History {
+    const RECENT_COUNT: usize = 20;
+
+    /// History of a session that starts in start, which recent lists from the beginning
+    pub fn new(start: &str) -> History {
+        let mut history = History::default();
+        history.remember(start);
+        history
+    }
+
+    fn remember(&mut self, path: &str) {
+        self.recent.retain(|recent| recent != path);
+        self.recent.insert(0, path.to_string());
+        self.recent.truncate(History::RECENT_COUNT);
+    }
+
+    /// Moves location to target, which back then undoes
+    pub fn navigate(&mut self, location: &mut String, target: &str) {
+        if location == target {
+            return;
+        }
+        self.back.push(std::mem::replace(location, target.to_string()));
+        self.forward.clear();
+        self.remember(target);
+    }
+
+    /// Moves location to the directory before it. False when there is none.
+    pub fn back(&mut self, location: &mut String) -> bool {
+        match self.back.pop() {
+            None => false,
+            Some(previous) => {
+                self.forward.push(std::mem::replace(location, previous));
+                self.remember(location);
+                true
+            }
+        }
+    }
+
+    /// Undoes back. False when there is nothing to go forward to.
+    pub fn forward(&mut self, location: &mut String) -> bool {
+        match self.forward.pop() {
+            None => false,
+            Some(next) => {
+                self.back.push(std::mem::replace(location, next));
+                self.remember(location);
+                true
+            }
+        }
+    }
+
+    /// Where cd - goes
+    pub fn previous(&self) -> Option<&str> {
+        self.back.last().map(|path| path.as_str())
+    }
+
+    pub fn recent(&self) -> &[String] {
+        &self.recent
+    }
+
+    /// Drops the paths that keep doesn't accept, e.g. directories that were deleted. location is
+    /// where the session is now, so back and forward don't stop there again.
+    pub fn retain(&mut self, location: &str, keep: impl Fn(&str) -> bool) {
+        for list in [&mut self.back, &mut self.forward, &mut self.recent] {
+            list.retain(|path| keep(path));
+        }
+        // Removing entries may leave the same directory twice in a row
+        for list in [&mut self.back, &mut self.forward] {
+            list.dedup();
+            if list.last().map(|path| path.as_str()) == Some(location) {
+                list.pop();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> FileSystemEntry {
+        FileSystemEntry::from_paths("/", &[
+            ("/a", FileSystemEntryType::Directory, 0),
+            ("/a/b", FileSystemEntryType::Directory, 0),
+            ("/a/b/c", FileSystemEntryType::Directory, 0),
+            ("/a/sibling", FileSystemEntryType::Directory, 0),
+            ("/a/file", FileSystemEntryType::File, 1),
+        ])
+    }
+
+    fn cd(root: &FileSystemEntry, location: &str, target: &str) -> Result<String, String> {
+        resolve(root, location, target, Some("/a/sibling")).map(|chain| chain.last().map(|entry| entry.path_string.to_string()).unwrap_or_default())
+    }
+
+    #[test]
+    fn test_resolve() {
+        let root = tree();
+        assert_eq!(cd(&root, "/", "a/b/c"), Ok(String::from("/a/b/c")));
+        assert_eq!(cd(&root, "/a/b", "../sibling"), Ok(String::from("/a/sibling")));
+        assert_eq!(cd(&root, "/a/b/c", "../../B/./c/"), Ok(String::from("/a/b/c")));
+        assert_eq!(cd(&root, "/a/b/c", "/a/b"), Ok(String::from("/a/b")));
+        assert_eq!(cd(&root, "/a/b/c", "/"), Ok(String::from("/")));
+        assert_eq!(cd(&root, "/a/b/c", "-"), Ok(String::from("/a/sibling")));
+        assert_eq!(resolve(&root, "/a/b", "a/b/c", Some("/a")).map(|chain| chain.len()).ok(), None);
+        assert_eq!(resolve(&root, "/a", "b/c", None).map(|chain| chain.len()), Ok(4));
+
+        assert!(cd(&root, "/", "..").is_err());
+        assert!(cd(&root, "/", "a/file").is_err());
+        assert!(cd(&root, "/", "a/missing/c").is_err());
+        assert!(resolve(&root, "/", "-", None).is_err());
+
+    }
+
+    #[test]
+    fn test_history() {
+        let mut location = String::from("/");
+        let mut history = History::new(&location);
+        assert_eq!(history.recent(), &["/"]);
+        history.navigate(&mut location, "/a");
+        history.navigate(&mut location, "/a/b");
+        history.navigate(&mut location, "/a/b");
+        assert_eq!(history.previous(), Some("/a"));
+
+        assert!(history.back(&mut location));
+        assert!(history.back(&mut location));
+        assert_eq!(location, "/");
+        assert!(!history.back(&mut location));
+        assert!(history.forward(&mut location));
+        assert_eq!(location, "/a");
+
+        // Going somewhere new drops what was ahead
+        history.navigate(&mut location, "/a/sibling");
+        assert!(!history.forward(&mut location));
+        assert_eq!(history.recent(), &["/a/sibling", "/a", "/", "/a/b"]);
+
+        history.retain(&location, |path| path != "/a");
+        assert_eq!(history.previous(), Some("/"));
+        assert_eq!(history.recent(), &["/a/sibling", "/", "/a/b"]);
+    }
+
+    #[test]
+    fn test_retain_after_removing_location() {
+        // cd /a, cd /a/b, then /a/b is deleted and the session moves up to /a
+        let mut location = String::from("/");
+        let mut history = History::new(&location);
+        history.navigate(&mut location, "/a");
+        history.navigate(&mut location, "/a/b");
+        history.navigate(&mut location, "/a");
+        history.retain(&location, |path| path != "/a/b");
+
+        // Back goes to where the session was before /a, not to /a again
+        assert_eq!(history.previous(), Some("/"));
+        assert!(history.back(&mut location));
+        assert_eq!(location, "/");
+        assert!(!history.back(&mut location));
+        assert_eq!(history.recent(), &["/", "/a"]);
+    }
+}
@@ -0,0 +1,103 @@
+use super::filesystem_entry::{
+    FileSystemEntry,
+    filesystem_entry_type::FileSystemEntryType,
+    filesystem_entry_extensions::*,
+};
+
+use std::collections::HashMap;
+
+#[derive(Default)]
+struct OwnerUsage {
+    bytes: u64,
+    files: u64,
+}
+
+// Parses the contents of an /etc/passwd or /etc/group style file into an id -> name map.
+// Both formats keep the name in the first field and the numeric id in the third.
+fn parse_id_names(contents: &str) -> HashMap<u32, String> {
+    let mut names = HashMap::<u32, String>::new();
+    for line in contents.lines() {
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let fields : Vec<&str> = line.split(':').collect();
+        if fields.len() < 3 {
+            continue;
+        }
+
+        if let Ok(id) = fields[2].parse::<u32>() {
+            names.entry(id).or_insert_with(|| fields[0].to_string());
+        }
+    }
+
+    names
+}
+
+fn read_id_names(path: &str) -> HashMap<u32, String> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => parse_id_names(&contents),
+        Err(_e) => HashMap::<u32, String>::new(),
+    }
+}
+
+fn print_usage(title: &str, usage: &HashMap<u32, OwnerUsage>, names: &HashMap<u32, String>, total_bytes: u64) {
+    let mut rows : Vec<(&u32, &OwnerUsage)> = usage.iter().collect();
+    rows.sort_by_key(|row| std::cmp::Reverse(row.1.bytes));
+
+    utils::log_i(title);
+    for (id, owner_usage) in rows {
+        let name = match names.get(id) {
+            Some(name) => name.to_string(),
+            None => id.to_string(),
+        };
+        let percent = if total_bytes == 0 { 0.0 } else { owner_usage.bytes as f64 * 100.0 / total_bytes as f64 };
+        utils::log(format!("   {:<16} {:>10} {:>5.1}%  {} files", name, owner_usage.bytes.bytes_to_readable(), percent, owner_usage.files).as_str());
+    }
+}
+
+/// Prints the bytes and file counts below fse aggregated per owning user and group
+pub fn print_owners(fse: &FileSystemEntry) {
+    let mut users = HashMap::<u32, OwnerUsage>::new();
+    let mut groups = HashMap::<u32, OwnerUsage>::new();
+    let mut total_bytes : u64 = 0;
+
+    fse.walk(&mut |entry: &FileSystemEntry| {
+        if let FileSystemEntryType::File = entry.entry_type {
+            let user = users.entry(entry.uid).or_default();
+            user.bytes += entry.len;
+            user.files += 1;
+
+            let group = groups.entry(entry.gid).or_default();
+            group.bytes += entry.len;
+            group.files += 1;
+
+            total_bytes += entry.len;
+        }
+    });
+
+    utils::log("");
+    utils::log(format!("\tOwners: {} ({})", fse.path_string, total_bytes.bytes_to_readable()).as_str());
+    utils::log("");
+
+    if users.is_empty() {
+        utils::log("No files");
+        return;
+    }
+
+    print_usage("Users", &users, &read_id_names("/etc/passwd"), total_bytes);
+    print_usage("Groups", &groups, &read_id_names("/etc/group"), total_bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_id_names() {
+        let names = parse_id_names("# comment\nroot:x:0:0:root:/root:/bin/bash\nquentin:x:1000:1000::/home/quentin:/bin/sh\nbroken\n");
+        assert_eq!(names.get(&0).map(String::as_str), Some("root"));
+        assert_eq!(names.get(&1000).map(String::as_str), Some("quentin"));
+        assert_eq!(names.len(), 2);
+    }
+}
@@ -0,0 +1,218 @@
+use super::filesystem_entry::{
+    FileSystemEntry,
+    excludes::Excludes,
+    filesystem_entry_type::FileSystemEntryType,
+    filesystem_entry_extensions::*,
+};
+use super::largest_files;
+use super::cli::{EXIT_OK, EXIT_FAILURE};
+
+use serde::Serialize;
+use std::io::Write;
+use std::path::PathBuf;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReportFormat {
+    Text,
+    Json,
+    Csv,
+}
+
+impl ReportFormat {
+    pub fn parse(name: &str) -> Result<ReportFormat, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "text" | "txt" => Ok(ReportFormat::Text),
+            "json" => Ok(ReportFormat::Json),
+            "csv" => Ok(ReportFormat::Csv),
+            _ => Err(format!("Invalid format '{}', expected text, json or csv", name)),
+        }
+    }
+}
+
+/// Options of the report subcommand
+#[derive(Debug)]
+pub struct ReportOptions {
+    pub path: PathBuf,
+    // Directories deeper than this below the path are not listed
+    pub depth: usize,
+    pub count: usize,
+    pub format: ReportFormat,
+}
+
+impl Default for ReportOptions {
+    fn default() -> ReportOptions {
+        ReportOptions {
+            path: PathBuf::from("."),
+            depth: 1,
+            count: 10,
+            format: ReportFormat::Text,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ReportEntry {
+    pub path: String,
+    pub size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub items: Option<u64>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Report {
+    pub path: String,
+    pub size: u64,
+    pub files: u64,
+    pub directories: u64,
+    pub top_directories: Vec<ReportEntry>,
+    pub top_files: Vec<ReportEntry>,
+}
+
+fn collect_directories<'a>(entry: &'a FileSystemEntry, depth: usize, max_depth: usize, found: &mut Vec<&'a FileSystemEntry>) {
+    if depth > max_depth {
+        return;
+    }
+    for child in entry.children().into_iter().flatten() {
+        if let FileSystemEntryType::Directory = child.entry_type {
+            found.push(child);
+            collect_directories(child, depth + 1, max_depth, found);
+        }
+    }
+}
+
+/// The largest directories down to depth levels below root and the largest files anywhere below it
+pub fn build_report(root: &FileSystemEntry, depth: usize, count: usize) -> Report {
+    let (mut files, mut directories) = (0, 0);
+    root.walk(&mut |entry: &FileSystemEntry| match entry.entry_type {
+        FileSystemEntryType::File => files += 1,
+        FileSystemEntryType::Directory => directories += 1,
+    });
+
+    let mut top_directories = Vec::<&FileSystemEntry>::new();
+    collect_directories(root, 1, depth, &mut top_directories);
+    top_directories.sort_by_key(|entry| std::cmp::Reverse(entry.len));
+    top_directories.truncate(count);
+
+    Report {
+        path: root.path_string.to_string(),
+        size: root.len,
+        files,
+        directories,
+        top_directories: top_directories.iter()
+            .map(|entry| ReportEntry { path: entry.path_string.to_string(), size: entry.len, items: Some(entry.item_count()) })
+            .collect(),
+        top_files: largest_files::largest_files(root, count).iter()
+            .map(|entry| ReportEntry { path: entry.path_string.to_string(), size: entry.len, items: None })
+            .collect(),
+    }
+}
+
+fn percent(size: u64, total: u64) -> f64 {
+    if total == 0 { 0.0 } else { size as f64 * 100.0 / total as f64 }
+}
+
+pub fn to_text(report: &Report) -> String {
+    let mut text = format!("Report: {}\nTotal: {} in {} files and {} directories\n",
+        report.path, report.size.bytes_to_readable(), report.files, report.directories);
+
+    text.push_str("\nLargest directories\n");
+    for entry in report.top_directories.iter() {
+        text.push_str(&format!("   {:>10} {:>5.1}% {:>8}  {}\n", entry.size.bytes_to_readable(), percent(entry.size, report.size), entry.items.unwrap_or(0), entry.path));
+    }
+    text.push_str("\nLargest files\n");
+    for entry in report.top_files.iter() {
+        text.push_str(&format!("   {:>10} {:>5.1}%  {}\n", entry.size.bytes_to_readable(), percent(entry.size, report.size), entry.path));
+    }
+    text
+}
+
+pub fn to_json(report: &Report) -> String {
+    serde_json::to_string_pretty(report).expect("reports always serialize") + "\n"
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// One row per listed entry. The total is the first row, with kind "total".
+pub fn to_csv(report: &Report) -> String {
+    let mut csv = String::from("kind,path,size,items\n");
+    csv.push_str(&format!("total,{},{},{}\n", csv_field(&report.path), report.size, report.files + report.directories));
+    for entry in report.top_directories.iter() {
+        csv.push_str(&format!("directory,{},{},{}\n", csv_field(&entry.path), entry.size, entry.items.unwrap_or(0)));
+    }
+    for entry in report.top_files.iter() {
+        csv.push_str(&format!("file,{},{},\n", csv_field(&entry.path), entry.size));
+    }
+    csv
+}
+
+/// Scans the report path and prints the report to stdout. Returns the exit code.
+pub fn run_report(options: &ReportOptions, excludes: &Excludes) -> i32 {
+    let path = match std::fs::canonicalize(&options.path) {
+        Ok(path) => path,
+        Err(e) => {
+            utils::log_e(format!("Cannot report on {}: {}", options.path.display(), e).as_str());
+            return EXIT_FAILURE;
+        }
+    };
+    let root = match FileSystemEntry::from_path(&path, excludes) {
+        Ok(root) => root,
+        Err(e) => {
+            utils::log_e(format!("Failed to scan {}: {}", path.display(), e).as_str());
+            return EXIT_FAILURE;
+        }
+    };
+
+    let report = build_report(&root, options.depth, options.count);
+    let output = match options.format {
+        ReportFormat::Text => to_text(&report),
+        ReportFormat::Json => to_json(&report),
+        ReportFormat::Csv => to_csv(&report),
+    };
+
+    match std::io::stdout().write_all(output.as_bytes()) {
+        Ok(()) => EXIT_OK,
+        Err(e) => {
+            utils::log_e(format!("Failed to write the report: {}", e).as_str());
+            EXIT_FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> FileSystemEntry {
+        FileSystemEntry::from_paths("/r", &[
+            ("/r/a", FileSystemEntryType::Directory, 0),
+            ("/r/a/b", FileSystemEntryType::Directory, 0),
+            ("/r/a/b/big", FileSystemEntryType::File, 500),
+            ("/r/x,y", FileSystemEntryType::File, 100),
+        ])
+    }
+
+    #[test]
+    fn test_build_report() {
+        let root = tree();
+        let report = build_report(&root, 1, 10);
+        assert_eq!((report.size, report.files, report.directories), (600, 2, 2));
+        assert_eq!(report.top_directories, vec![ReportEntry { path: String::from("/r/a"), size: 500, items: Some(2) }]);
+        assert_eq!(report.top_files.len(), 2);
+
+        assert_eq!(build_report(&root, 2, 10).top_directories.len(), 2);
+        assert_eq!(build_report(&root, 2, 1).top_files[0].path, "/r/a/b/big");
+    }
+
+    #[test]
+    fn test_csv_quotes_fields() {
+        let csv = to_csv(&build_report(&tree(), 1, 10));
+        assert!(csv.starts_with("kind,path,size,items\ntotal,/r,600,4\ndirectory,/r/a,500,2\n"));
+        assert!(csv.contains("file,\"/r/x,y\",100,\n"));
+    }
+}
@@ -0,0 +1,57 @@
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// Commands read from a file given with --script, or from stdin when it isn't a terminal
+pub struct Script {
+    // None reads stdin. It isn't locked between lines so that confirmations can read it too.
+    file: Option<Box<dyn BufRead>>,
+    pub name: String,
+    pub line: usize,
+}
+
+impl Script {
+    pub fn open(path: &Path) -> std::io::Result<Script> {
+        let file = std::fs::File::open(path)?;
+        Ok(Script { file: Some(Box::new(BufReader::new(file))), name: path.display().to_string(), line: 0 })
+    }
+
+    pub fn stdin() -> Script {
+        Script { file: None, name: String::from("stdin"), line: 0 }
+    }
+
+    /// The next command, skipping blank lines and # comments. None at the end of the script.
+    pub fn next_command(&mut self) -> std::io::Result<Option<String>> {
+        loop {
+            let mut text = String::new();
+            let read = match self.file.as_mut() {
+                Some(file) => file.read_line(&mut text)?,
+                None => std::io::stdin().read_line(&mut text)?,
+            };
+            if read == 0 {
+                return Ok(None);
+            }
+            self.line += 1;
+
+            let command = text.trim();
+            if !command.is_empty() && !command.starts_with('#') {
+                return Ok(Some(command.to_string()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_command() {
+        let text = "# setup\ncd tmp\n\n  top 5  \n";
+        let mut script = Script { file: Some(Box::new(text.as_bytes())), name: String::from("test"), line: 0 };
+        assert_eq!(script.next_command().expect("readable").as_deref(), Some("cd tmp"));
+        assert_eq!(script.line, 2);
+        assert_eq!(script.next_command().expect("readable").as_deref(), Some("top 5"));
+        assert_eq!(script.line, 4);
+        assert_eq!(script.next_command().expect("readable"), None);
+    }
+}
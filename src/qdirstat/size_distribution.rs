@@ -0,0 +1,104 @@
+use super::filesystem_entry::{
+    FileSystemEntry,
+    filesystem_entry_type::FileSystemEntryType,
+    filesystem_entry_extensions::*,
+};
+
+const BAR_WIDTH: usize = 40;
+
+// Nearest-rank percentile over an ascending list of sizes
+fn percentile(sorted_sizes: &[u64], p: f64) -> u64 {
+    if sorted_sizes.is_empty() {
+        return 0;
+    }
+
+    let rank = (p / 100.0 * sorted_sizes.len() as f64).ceil() as usize;
+    sorted_sizes[rank.clamp(1, sorted_sizes.len()) - 1]
+}
+
+// Files of size 0 go into bucket 0, everything else into bucket floor(log2(size)) + 1
+fn bucket(size: u64) -> usize {
+    if size == 0 {
+        0
+    } else {
+        (64 - size.leading_zeros()) as usize
+    }
+}
+
+fn bucket_label(bucket: usize) -> String {
+    if bucket == 0 {
+        return String::from("empty");
+    }
+
+    let low : u64 = 1 << (bucket - 1);
+    format!("{} - {}", low.bytes_to_readable(), low.saturating_mul(2).bytes_to_readable())
+}
+
+/// Prints a log2 histogram of the file sizes below fse with its percentiles and how many
+/// bytes sit in the files above each of them
+pub fn print_size_distribution(fse: &FileSystemEntry) {
+    let mut sizes = Vec::<u64>::new();
+    fse.walk(&mut |entry: &FileSystemEntry| {
+        if let FileSystemEntryType::File = entry.entry_type {
+            sizes.push(entry.len);
+        }
+    });
+
+    utils::log("");
+    utils::log(format!("\tSize distribution: {} ({} files)", fse.path_string, sizes.len()).as_str());
+    utils::log("");
+
+    if sizes.is_empty() {
+        utils::log("No files");
+        return;
+    }
+
+    sizes.sort_unstable();
+    let total_bytes : u64 = sizes.iter().sum();
+
+    let mut counts = vec![0u64; bucket(*sizes.last().expect("sizes is not empty")) + 1];
+    for size in sizes.iter() {
+        counts[bucket(*size)] += 1;
+    }
+
+    let first = counts.iter().position(|count| *count > 0).unwrap_or(0);
+    let max_count = *counts.iter().max().unwrap_or(&1);
+    for (idx, count) in counts.iter().enumerate().skip(first) {
+        let bar = "█".repeat((*count as usize * BAR_WIDTH).div_ceil(max_count as usize));
+        utils::log(format!("   {:>22} {:>8} {}", bucket_label(idx), count, bar).as_str());
+    }
+
+    utils::log("");
+    utils::log_i(format!("   {:<6} {:>10} {:>11} {:>11} {:>6}", "", "Size", "Files above", "Bytes above", "%").as_str());
+    for (label, p) in [("P50", 50.0), ("P90", 90.0), ("P99", 99.0)].iter() {
+        let threshold = percentile(&sizes, *p);
+        let above : Vec<&u64> = sizes.iter().filter(|size| **size > threshold).collect();
+        let above_bytes : u64 = above.iter().copied().sum();
+        let percent = if total_bytes == 0 { 0.0 } else { above_bytes as f64 * 100.0 / total_bytes as f64 };
+        utils::log(format!("   {:<6} {:>10} {:>11} {:>11} {:>5.1}%", label, threshold.bytes_to_readable(), above.len(), above_bytes.bytes_to_readable(), percent).as_str());
+    }
+    utils::log(format!("   {:<6} {:>10}", "Max", sizes.last().expect("sizes is not empty").bytes_to_readable()).as_str());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile() {
+        let sizes : Vec<u64> = (1..=100).collect();
+        assert_eq!(percentile(&sizes, 50.0), 50);
+        assert_eq!(percentile(&sizes, 90.0), 90);
+        assert_eq!(percentile(&sizes, 99.0), 99);
+        assert_eq!(percentile(&[7], 99.0), 7);
+        assert_eq!(percentile(&[], 50.0), 0);
+    }
+
+    #[test]
+    fn test_bucket() {
+        assert_eq!(bucket(0), 0);
+        assert_eq!(bucket(1), 1);
+        assert_eq!(bucket(1023), 10);
+        assert_eq!(bucket(1024), 11);
+    }
+}
@@ -0,0 +1,431 @@
+use super::filesystem_entry::{
+    FileSystemEntry,
+    excludes::Excludes,
+    filesystem_entry_extensions::*,
+};
+
+use chrono::Local;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+// Implements the freedesktop.org Trash specification:
+// https://specifications.freedesktop.org/trash-spec/trashspec-latest.html
+
+const INFO_EXTENSION: &str = "trashinfo";
+
+pub struct TrashItem {
+    trash_dir: PathBuf,
+    name: String,
+    pub original: PathBuf,
+    pub deleted: String,
+}
+
+// Size of the file or directory at path, without following symlinks. Unreadable entries count
+// as empty.
+fn disk_usage(path: &Path) -> u64 {
+    match fs::symlink_metadata(path) {
+        Err(_e) => 0,
+        Ok(metadata) if metadata.is_dir() => fs::read_dir(path)
+            .map(|children| children.flatten().map(|child| disk_usage(&child.path())).sum())
+            .unwrap_or(0),
+        Ok(metadata) => metadata.len(),
+    }
+}
+
+impl TrashItem {
+    fn file_path(&self) -> PathBuf {
+        self.trash_dir.join("files").join(&self.name)
+    }
+
+    fn info_path(&self) -> PathBuf {
+        self.trash_dir.join("info").join(format!("{}.{}", self.name, INFO_EXTENSION))
+    }
+
+    /// Size of the trashed entry, which for a directory means walking it
+    pub fn size(&self) -> u64 {
+        disk_usage(&self.file_path())
+    }
+}
+
+// Bytes outside of the unreserved URI characters are percent-encoded, '/' is kept as is
+fn percent_encode(path: &str) -> String {
+    let mut encoded = String::new();
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+fn percent_decode(encoded: &str) -> String {
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::<u8>::new();
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let escaped = encoded.get(idx + 1..idx + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[idx], escaped) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                idx += 3;
+            },
+            (byte, _) => {
+                decoded.push(byte);
+                idx += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+fn trash_info(original: &Path, deleted: &str) -> String {
+    format!("[Trash Info]\nPath={}\nDeletionDate={}\n", percent_encode(&original.to_string_lossy()), deleted)
+}
+
+// Original path and deletion date of a .trashinfo file. Relative paths are relative to the top
+// directory of the volume, which is the parent of the trash directory's parent for .Trash/$uid.
+fn parse_trash_info(contents: &str, top_dir: &Path) -> Option<(PathBuf, String)> {
+    let mut lines = contents.lines().map(|line| line.trim()).filter(|line| !line.is_empty());
+    if lines.next()? != "[Trash Info]" {
+        return None;
+    }
+
+    let mut original : Option<PathBuf> = None;
+    let mut deleted = String::new();
+    for line in lines {
+        if line.starts_with('[') {
+            break;
+        }
+        match line.split_once('=') {
+            Some(("Path", path)) => original = Some(top_dir.join(percent_decode(path))),
+            Some(("DeletionDate", date)) => deleted = date.to_string(),
+            _ => {}
+        }
+    }
+    original.map(|original| (original, deleted))
+}
+
+fn home_trash() -> Option<PathBuf> {
+    let data_home = std::env::var("XDG_DATA_HOME").ok()
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| std::env::var("HOME").ok().map(|home| Path::new(&home).join(".local").join("share")))?;
+    Some(data_home.join("Trash"))
+}
+
+#[cfg(unix)]
+fn device(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    fs::symlink_metadata(path).ok().map(|metadata| metadata.dev())
+}
+
+#[cfg(not(unix))]
+fn device(_path: &Path) -> Option<u64> {
+    None
+}
+
+#[cfg(unix)]
+fn user_id() -> io::Result<u32> {
+    use std::os::unix::fs::MetadataExt;
+    // /proc/self belongs to the user running this process, which saves calling into libc. Without
+    // it or a home directory the volume trash is unknown, guessing would pick root's.
+    fs::metadata("/proc/self")
+        .or_else(|_e| fs::metadata(std::env::var("HOME").unwrap_or_default()))
+        .map(|metadata| metadata.uid())
+        .map_err(|_e| io::Error::new(io::ErrorKind::NotFound, "Can't tell which user is running, so not which trash to use"))
+}
+
+#[cfg(not(unix))]
+fn user_id() -> io::Result<u32> {
+    Ok(0)
+}
+
+// The deepest existing directory of path, so that devices can be compared for paths about to be created
+fn existing_ancestor(path: &Path) -> &Path {
+    let mut path = path;
+    while !path.exists() {
+        match path.parent() {
+            Some(parent) => path = parent,
+            None => break,
+        }
+    }
+    path
+}
+
+// Mount point of the volume holding path
+fn top_dir(path: &Path) -> PathBuf {
+    let path = fs::canonicalize(path).unwrap_or_else(|_e| path.to_path_buf());
+    let dev = device(&path);
+    let mut top = path.as_path();
+    while let Some(parent) = top.parent() {
+        if device(parent) != dev {
+            break;
+        }
+        top = parent;
+    }
+    top.to_path_buf()
+}
+
+#[cfg(unix)]
+fn is_sticky_dir(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata.is_dir() && metadata.permissions().mode() & 0o1000 != 0,
+        Err(_e) => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn is_sticky_dir(_path: &Path) -> bool {
+    false
+}
+
+// Trash directories of the volume holding path: $topdir/.Trash/$uid when the administrator set
+// up a shared .Trash, and $topdir/.Trash-$uid otherwise
+fn volume_trashes(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let top = top_dir(path);
+    let uid = user_id()?;
+    let mut trashes = Vec::<PathBuf>::new();
+    if is_sticky_dir(&top.join(".Trash")) {
+        trashes.push(top.join(".Trash").join(uid.to_string()));
+    }
+    trashes.push(top.join(format!(".Trash-{}", uid)));
+    Ok(trashes)
+}
+
+// The trash directory an entry at path goes to. Files on the home volume use the home trash,
+// everything else the trash of its own volume so that it can be moved rather than copied.
+fn trash_dir_for(path: &Path) -> io::Result<PathBuf> {
+    let home = home_trash().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No home directory to hold the trash"))?;
+    if device(existing_ancestor(&home)) == device(path) {
+        return Ok(home);
+    }
+
+    let trash = volume_trashes(path)?.remove(0);
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(&trash)?;
+    Ok(trash)
+}
+
+fn trash_dirs(path: &Path) -> Vec<PathBuf> {
+    let mut dirs : Vec<PathBuf> = home_trash().into_iter().collect();
+    // Only the home trash can be listed when the user is unknown
+    for trash in volume_trashes(path).unwrap_or_default() {
+        if !dirs.contains(&trash) {
+            dirs.push(trash);
+        }
+    }
+    dirs
+}
+
+/// Moves the file or directory at path into the trash and writes its .trashinfo
+pub fn trash_path(path: &Path) -> io::Result<()> {
+    let trash = trash_dir_for(path)?;
+    let files = trash.join("files");
+    let info = trash.join("info");
+    fs::create_dir_all(&files)?;
+    fs::create_dir_all(&info)?;
+
+    let name = path.file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Path has no file name"))?
+        .to_string_lossy()
+        .to_string();
+    let deleted = Local::now().format("%Y-%m-%dT%H:%M:%S").to_string();
+
+    // Creating the info file first and exclusively reserves the name in the trash
+    for attempt in 1.. {
+        let trashed_name = if attempt == 1 { name.to_string() } else { format!("{}.{}", name, attempt) };
+        let info_path = info.join(format!("{}.{}", trashed_name, INFO_EXTENSION));
+        let mut info_file = match fs::OpenOptions::new().write(true).create_new(true).open(&info_path) {
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            other => other?,
+        };
+        if files.join(&trashed_name).exists() {
+            // Leftover without info file, keep it and try the next name
+            drop(info_file);
+            fs::remove_file(&info_path)?;
+            continue;
+        }
+
+        let write = info_file.write_all(trash_info(path, &deleted).as_bytes())
+            .and_then(|_| fs::rename(path, files.join(&trashed_name)));
+        if let Err(e) = write {
+            fs::remove_file(&info_path).ok();
+            return Err(e);
+        }
+        return Ok(());
+    }
+    unreachable!("names to try never run out")
+}
+
+/// Everything in the home trash and in the trash of the volume holding path, most recently
+/// deleted first
+pub fn list_trash(path: &Path) -> Vec<TrashItem> {
+    let mut items = Vec::<TrashItem>::new();
+    for trash_dir in trash_dirs(path) {
+        let top = if trash_dir.parent().and_then(|parent| parent.file_name()) == Some(std::ffi::OsStr::new(".Trash")) {
+            trash_dir.parent().and_then(|parent| parent.parent()).unwrap_or(&trash_dir).to_path_buf()
+        } else {
+            trash_dir.parent().unwrap_or(&trash_dir).to_path_buf()
+        };
+
+        let infos = match fs::read_dir(trash_dir.join("info")) {
+            Ok(infos) => infos,
+            Err(_e) => continue,
+        };
+        for info in infos.flatten() {
+            let info_path = info.path();
+            if info_path.extension() != Some(std::ffi::OsStr::new(INFO_EXTENSION)) {
+                continue;
+            }
+            let name = match info_path.file_stem() {
+                Some(name) => name.to_string_lossy().to_string(),
+                None => continue,
+            };
+            let parsed = fs::read_to_string(&info_path).ok().and_then(|contents| parse_trash_info(&contents, &top));
+            if let Some((original, deleted)) = parsed {
+                items.push(TrashItem { trash_dir: trash_dir.to_path_buf(), name, original, deleted });
+            }
+        }
+    }
+
+    items.sort_by(|a, b| b.deleted.cmp(&a.deleted));
+    items
+}
+
+/// Moves a trashed entry back to where it came from and removes its .trashinfo
+pub fn restore(item: &TrashItem) -> io::Result<()> {
+    if fs::symlink_metadata(&item.original).is_ok() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, "Something else exists at the original location"));
+    }
+    if let Some(parent) = item.original.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(item.file_path(), &item.original)?;
+    fs::remove_file(item.info_path())
+}
+
+/// Moves the entries at paths to the trash and removes them from the tree below root. Returns
+/// the paths that were trashed, and false when any of them failed; failures are reported per path.
+pub fn trash_entries(root: &mut FileSystemEntry, paths: &[String]) -> (Vec<String>, bool) {
+    let mut trashed = Vec::<String>::new();
+    let mut total : u64 = 0;
+    let paths = super::delete::outermost(paths);
+    let count = paths.len();
+    for path in paths {
+        match trash_path(Path::new(&path)) {
+            Err(e) => utils::log_e(format!("Failed to move {} to the trash: {}", path, e).as_str()),
+            Ok(()) => {
+                if let Some(removed) = root.remove(&path) {
+                    total += removed.len;
+                }
+                utils::log(format!("Trashed {}", path).as_str());
+                trashed.push(path);
+            }
+        }
+    }
+
+    if !trashed.is_empty() {
+        utils::log_s(format!("Moved {} entries ({}) to the trash", trashed.len(), total.bytes_to_readable()).as_str());
+    }
+    let succeeded = trashed.len() == count;
+    (trashed, succeeded)
+}
+
+/// Prints the trash contents numbered for trash restore
+pub fn print_trash(path: &Path) {
+    let items = list_trash(path);
+    utils::log("");
+    utils::log(format!("\tTrash ({} entries)", items.len()).as_str());
+    utils::log("");
+    if items.is_empty() {
+        utils::log("The trash is empty");
+        return;
+    }
+
+    for (idx, item) in items.iter().enumerate() {
+        let number = format!("[{}]", idx + 1);
+        utils::log(format!("   {:>5} {:>10}  {:<19}  {}", number, item.size().bytes_to_readable(), item.deleted, item.original.display()).as_str());
+    }
+    utils::log("");
+    utils::log("Use 'trash restore <number>' to put an entry back");
+}
+
+/// Restores the trash entries with the given numbers from print_trash and adds them back to the
+/// tree below root. False when any of them could not be restored.
+pub fn restore_entries(root: &mut FileSystemEntry, path: &Path, numbers: &[String], excludes: &Excludes) -> bool {
+    let items = list_trash(path);
+    let mut chosen = Vec::<&TrashItem>::new();
+    for number in numbers.iter().filter(|number| !number.is_empty()) {
+        match number.parse::<usize>().ok().and_then(|number| number.checked_sub(1)).and_then(|idx| items.get(idx)) {
+            Some(item) => chosen.push(item),
+            None => {
+                utils::log_w(format!("No trash entry numbered '{}', see trash list", number).as_str());
+                return false;
+            }
+        }
+    }
+    if chosen.is_empty() {
+        utils::log_w("Restore requires the number of a trash entry (e.g. trash restore 2)");
+        return false;
+    }
+
+    let mut succeeded = true;
+    for item in chosen {
+        match restore(item) {
+            Err(e) => {
+                utils::log_e(format!("Failed to restore {}: {}", item.original.display(), e).as_str());
+                succeeded = false;
+            },
+            Ok(()) => {
+                if let Ok(entry) = FileSystemEntry::from_path(&item.original, excludes) {
+                    root.insert(entry);
+                }
+                utils::log_s(format!("Restored {}", item.original.display()).as_str());
+            }
+        }
+    }
+    succeeded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percent_encoding() {
+        let path = "/home/me/My Files/50% off/ü.txt";
+        assert_eq!(percent_encode(path), "/home/me/My%20Files/50%25%20off/%C3%BC.txt");
+        assert_eq!(percent_decode(&percent_encode(path)), path);
+        assert_eq!(percent_decode("100%"), "100%");
+    }
+
+    #[test]
+    fn test_trash_info_round_trip() {
+        let info = trash_info(Path::new("/data/old logs"), "2024-02-29T13:37:00");
+        assert_eq!(info, "[Trash Info]\nPath=/data/old%20logs\nDeletionDate=2024-02-29T13:37:00\n");
+        assert_eq!(parse_trash_info(&info, Path::new("/mnt")), Some((PathBuf::from("/data/old logs"), String::from("2024-02-29T13:37:00"))));
+        assert_eq!(parse_trash_info("Path=a\nDeletionDate=b", Path::new("/mnt")), None);
+        assert_eq!(parse_trash_info("[Trash Info]\nPath=photos/a.jpg\n", Path::new("/mnt")).map(|parsed| parsed.0), Some(PathBuf::from("/mnt/photos/a.jpg")));
+    }
+
+    #[test]
+    fn test_disk_usage() {
+        let dir = std::env::temp_dir().join(format!("qdirstat-trash-{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub")).expect("create test directory");
+        fs::write(dir.join("a"), vec![0u8; 300]).expect("write test file");
+        fs::write(dir.join("sub").join("b"), vec![0u8; 50]).expect("write test file");
+        let usage = (disk_usage(&dir), disk_usage(&dir.join("a")), disk_usage(&dir.join("missing")));
+        fs::remove_dir_all(&dir).expect("remove test directory");
+        assert_eq!(usage, (350, 300, 0));
+    }
+}
@@ -0,0 +1,270 @@
+use super::filesystem_entry::{
+    FileSystemEntry,
+    filesystem_entry_type::FileSystemEntryType,
+    filesystem_entry_extensions::*,
+};
+use super::file_category::{self, FileCategory};
+use super::tui;
+
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode},
+    queue,
+    style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor},
+    terminal,
+};
+use std::io::Write;
+
+// Terminal cells are roughly twice as tall as they are wide. Layouts are computed on a grid with
+// doubled rows so that the blocks come out square on screen.
+const CELL_ASPECT: f64 = 2.0;
+
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub w: f64,
+    pub h: f64,
+}
+
+// Worst aspect ratio of a row of areas laid along a side, see Bruls, Huizing and van Wijk
+fn worst_ratio(sum: f64, largest: f64, smallest: f64, side: f64) -> f64 {
+    let side_squared = side * side;
+    let sum_squared = sum * sum;
+    f64::max(side_squared * largest / sum_squared, sum_squared / (side_squared * smallest))
+}
+
+/// Squarified treemap layout. Returns one rectangle per size, in the same order as sizes.
+pub fn squarify(sizes: &[u64], bounds: Rect) -> Vec<Rect> {
+    let mut rects = vec![Rect::default(); sizes.len()];
+    let total : u64 = sizes.iter().sum();
+    if total == 0 || bounds.w <= 0.0 || bounds.h <= 0.0 {
+        return rects;
+    }
+
+    let scale = bounds.w * bounds.h / total as f64;
+    let mut order : Vec<usize> = (0..sizes.len()).filter(|idx| sizes[*idx] > 0).collect();
+    order.sort_by_key(|idx| std::cmp::Reverse(sizes[*idx]));
+    let area = |position: usize| sizes[order[position]] as f64 * scale;
+
+    let mut remaining = bounds;
+    let mut start = 0;
+    while start < order.len() {
+        let side = remaining.w.min(remaining.h);
+
+        // Items are sorted largest first, so the first item of a row is its largest and the last its smallest
+        let mut end = start + 1;
+        let mut row_area = area(start);
+        while end < order.len() {
+            let grown = row_area + area(end);
+            if worst_ratio(grown, area(start), area(end), side) > worst_ratio(row_area, area(start), area(end - 1), side) {
+                break;
+            }
+            row_area = grown;
+            end += 1;
+        }
+
+        if remaining.w >= remaining.h {
+            let strip = row_area / remaining.h;
+            let mut y = remaining.y;
+            for position in start..end {
+                let h = area(position) / strip;
+                rects[order[position]] = Rect { x: remaining.x, y, w: strip, h };
+                y += h;
+            }
+            remaining.x += strip;
+            remaining.w -= strip;
+        } else {
+            let strip = row_area / remaining.w;
+            let mut x = remaining.x;
+            for position in start..end {
+                let w = area(position) / strip;
+                rects[order[position]] = Rect { x, y: remaining.y, w, h: strip };
+                x += w;
+            }
+            remaining.y += strip;
+            remaining.h -= strip;
+        }
+
+        start = end;
+    }
+
+    rects
+}
+
+struct Block {
+    rect: Rect,
+    category: FileCategory,
+}
+
+// Lays out entry inside rect, subdividing directories for as long as their blocks are big enough
+fn layout(entry: &FileSystemEntry, rect: Rect, blocks: &mut Vec<Block>) {
+    let children = match entry.children() {
+        Some(children) if !children.is_empty() && rect.w >= 2.0 && rect.h >= 2.0 * CELL_ASPECT => children,
+        _ => {
+            blocks.push(Block { rect, category: file_category::category_of(entry) });
+            return;
+        }
+    };
+
+    let sizes : Vec<u64> = children.iter().map(|child| child.len).collect();
+    for (child, child_rect) in children.iter().zip(squarify(&sizes, rect)) {
+        if child.len > 0 {
+            layout(child, child_rect, blocks);
+        }
+    }
+}
+
+// Whole cells covered by a rectangle given in layout coordinates
+fn cells(rect: &Rect) -> (u16, u16, u16, u16) {
+    (
+        rect.x.round() as u16,
+        (rect.y / CELL_ASPECT).round() as u16,
+        (rect.x + rect.w).round() as u16,
+        ((rect.y + rect.h) / CELL_ASPECT).round() as u16,
+    )
+}
+
+// Neighbouring blocks of the same category alternate in brightness so they stay distinguishable
+fn shade(color: Color, darker: bool) -> Color {
+    match (color, darker) {
+        (Color::Rgb { r, g, b }, true) => Color::Rgb { r: r / 4 * 3, g: g / 4 * 3, b: b / 4 * 3 },
+        _ => color,
+    }
+}
+
+struct Treemap<'a> {
+    children: Vec<&'a FileSystemEntry>,
+    rects: Vec<Rect>,
+    grid: Vec<Vec<(Color, char)>>,
+    width: u16,
+}
+
+impl<'a> Treemap<'a> {
+    fn new(fse: &'a FileSystemEntry, width: u16, height: u16) -> Treemap<'a> {
+        let mut children : Vec<&FileSystemEntry> = match fse.children() {
+            Some(children) => children.iter().filter(|child| child.len > 0).collect(),
+            None => Vec::<&FileSystemEntry>::new(),
+        };
+        children.sort_by_key(|child| std::cmp::Reverse(child.len));
+
+        let bounds = Rect { x: 0.0, y: 0.0, w: width as f64, h: height as f64 * CELL_ASPECT };
+        let sizes : Vec<u64> = children.iter().map(|child| child.len).collect();
+        let rects = squarify(&sizes, bounds);
+
+        let mut grid = vec![vec![(Color::Black, ' '); width as usize]; height as usize];
+        let mut blocks = Vec::<Block>::new();
+        for (child, rect) in children.iter().zip(rects.iter()) {
+            layout(child, *rect, &mut blocks);
+        }
+        for (idx, block) in blocks.iter().enumerate() {
+            let (x0, y0, x1, y1) = cells(&block.rect);
+            let color = shade(block.category.color(), idx % 2 == 1);
+            for row in grid.iter_mut().take(y1.min(height) as usize).skip(y0 as usize) {
+                for cell in row.iter_mut().take(x1.min(width) as usize).skip(x0 as usize) {
+                    *cell = (color, ' ');
+                }
+            }
+        }
+
+        // Label the top level blocks wherever their name fits
+        for (child, rect) in children.iter().zip(rects.iter()) {
+            let (x0, y0, x1, y1) = cells(rect);
+            if y1 <= y0 || x1 <= x0 + 2 || y0 >= height {
+                continue;
+            }
+            let label : Vec<char> = format!("{} {}", child.identifier, child.len.bytes_to_readable()).chars().collect();
+            for (offset, c) in label.iter().take((x1 - x0) as usize).enumerate() {
+                if let Some(cell) = grid[y0 as usize].get_mut(x0 as usize + offset) {
+                    cell.1 = *c;
+                }
+            }
+        }
+
+        Treemap { children, rects, grid, width }
+    }
+
+    fn draw(&self, out: &mut impl Write, selected: usize, status: &str) -> crossterm::Result<()> {
+        let selection = self.rects.get(selected).map(cells);
+        for (y, row) in self.grid.iter().enumerate() {
+            queue!(out, cursor::MoveTo(0, y as u16))?;
+            for (x, (color, c)) in row.iter().enumerate() {
+                let mut glyph = *c;
+                if let Some((x0, y0, x1, y1)) = selection {
+                    let (x, y) = (x as u16, y as u16);
+                    let inside = x >= x0 && x < x1 && y >= y0 && y < y1;
+                    if inside && (x == x0 || x + 1 == x1) {
+                        glyph = '│';
+                    }
+                    if inside && (y == y0 || y + 1 == y1) && glyph == ' ' {
+                        glyph = '─';
+                    }
+                }
+                queue!(out, SetBackgroundColor(*color), SetForegroundColor(Color::White), Print(glyph))?;
+            }
+        }
+
+        let status : String = status.chars().take(self.width as usize).collect();
+        queue!(out, ResetColor, cursor::MoveTo(0, self.grid.len() as u16), terminal::Clear(terminal::ClearType::CurrentLine), Print(status))?;
+        out.flush()?;
+        Ok(())
+    }
+}
+
+fn interact(fse: &FileSystemEntry, out: &mut impl Write) -> crossterm::Result<Option<String>> {
+    let mut selected = 0;
+    loop {
+        let (width, height) = terminal::size()?;
+        let treemap = Treemap::new(fse, width, height.saturating_sub(1));
+        if treemap.children.is_empty() {
+            return Ok(None);
+        }
+        selected = selected.min(treemap.children.len() - 1);
+
+        let child = treemap.children[selected];
+        let status = format!(" {:?} {} ({})  ←/→ select  Enter open  Esc quit", child.entry_type, child.identifier, child.len.bytes_to_readable());
+        treemap.draw(out, selected, &status)?;
+
+        if let Event::Key(key) = event::read()? {
+            match key.code {
+                KeyCode::Right | KeyCode::Down | KeyCode::Tab => selected = (selected + 1) % treemap.children.len(),
+                KeyCode::Left | KeyCode::Up | KeyCode::BackTab => selected = (selected + treemap.children.len() - 1) % treemap.children.len(),
+                KeyCode::Enter => {
+                    if let FileSystemEntryType::Directory = child.entry_type {
+                        return Ok(Some(child.path_string.to_string()));
+                    }
+                },
+                KeyCode::Esc | KeyCode::Char('q') => return Ok(None),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Draws a full screen treemap of fse, colored by file type. Returns the path of the directory
+/// the user selected, if any.
+pub fn show_treemap(fse: &FileSystemEntry) -> crossterm::Result<Option<String>> {
+    tui::fullscreen(|out| interact(fse, out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_squarify_covers_bounds() {
+        let bounds = Rect { x: 0.0, y: 0.0, w: 60.0, h: 40.0 };
+        let sizes = [6, 6, 4, 3, 2, 2, 1, 0];
+        let rects = squarify(&sizes, bounds);
+
+        let total_area : f64 = rects.iter().map(|rect| rect.w * rect.h).sum();
+        assert!((total_area - bounds.w * bounds.h).abs() < 1e-6);
+
+        // Every block gets an area proportional to its size and stays inside the bounds
+        for (size, rect) in sizes.iter().zip(rects.iter()) {
+            assert!((rect.w * rect.h - *size as f64 * 100.0).abs() < 1e-6);
+            assert!(rect.x >= 0.0 && rect.x + rect.w <= bounds.w + 1e-6);
+            assert!(rect.y >= 0.0 && rect.y + rect.h <= bounds.h + 1e-6);
+        }
+    }
+}
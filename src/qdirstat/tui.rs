@@ -0,0 +1,284 @@
+use super::filesystem_entry::{
+    FileSystemEntry,
+    filesystem_entry_type::FileSystemEntryType,
+    filesystem_entry_extensions::*,
+    sort_order::SortOrder,
+};
+
+use console::{pad_str, Alignment};
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode},
+    queue,
+    style::{Attribute, Color, Print, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor},
+    terminal,
+};
+use std::collections::HashSet;
+use std::io::{Stdout, Write};
+
+const BAR_WIDTH: usize = 20;
+
+/// Runs f on the alternate screen with raw mode enabled and restores the terminal afterwards,
+/// whether f succeeded or not
+pub fn fullscreen<T>(f: impl FnOnce(&mut Stdout) -> crossterm::Result<T>) -> crossterm::Result<T> {
+    let mut out = std::io::stdout();
+    terminal::enable_raw_mode()?;
+    queue!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+
+    let result = f(&mut out);
+
+    queue!(out, ResetColor, cursor::Show, terminal::LeaveAlternateScreen)?;
+    out.flush()?;
+    terminal::disable_raw_mode()?;
+    result
+}
+
+struct Row<'a> {
+    entry: &'a FileSystemEntry,
+    depth: usize,
+    parent_len: u64,
+}
+
+impl Row<'_> {
+    /// Share of the parent directory's size
+    fn fraction(&self) -> f64 {
+        if self.parent_len == 0 { 0.0 } else { self.entry.len as f64 / self.parent_len as f64 }
+    }
+}
+
+fn count_files(entry: &FileSystemEntry) -> u64 {
+    let mut files : u64 = 0;
+    entry.walk(&mut |entry: &FileSystemEntry| {
+        if let FileSystemEntryType::File = entry.entry_type {
+            files += 1;
+        }
+    });
+    files
+}
+
+struct Browser<'a> {
+    // Directories from the scan root down to the one being browsed
+    chain: Vec<&'a FileSystemEntry>,
+    // Files below the directory being browsed, counted when it is entered rather than on every redraw
+    files: u64,
+    expanded: HashSet<String>,
+    order: SortOrder,
+    selected: usize,
+    offset: usize,
+}
+
+impl<'a> Browser<'a> {
+    fn new(chain: Vec<&'a FileSystemEntry>, order: &SortOrder) -> Browser<'a> {
+        let files = chain.last().map(|current| count_files(current)).unwrap_or(0);
+        Browser {
+            chain,
+            files,
+            expanded: HashSet::<String>::new(),
+            order: *order,
+            selected: 0,
+            offset: 0,
+        }
+    }
+
+    fn current(&self) -> &'a FileSystemEntry {
+        self.chain.last().expect("browser chain always holds the root")
+    }
+
+    fn push_rows(&self, entry: &'a FileSystemEntry, depth: usize, rows: &mut Vec<Row<'a>>) {
+        if let Some(children) = entry.children() {
+            let mut sorted : Vec<&FileSystemEntry> = children.iter().collect();
+            self.order.sort(&mut sorted);
+            for child in sorted {
+                rows.push(Row { entry: child, depth, parent_len: entry.len });
+                if self.expanded.contains(&child.path_string) {
+                    self.push_rows(child, depth + 1, rows);
+                }
+            }
+        }
+    }
+
+    fn rows(&self) -> Vec<Row<'a>> {
+        let mut rows = Vec::<Row>::new();
+        self.push_rows(self.current(), 0, &mut rows);
+        rows
+    }
+
+    fn descend(&mut self, entry: &'a FileSystemEntry) {
+        if let FileSystemEntryType::Directory = entry.entry_type {
+            // The entry may sit anywhere inside the expanded tree, so rebuild the chain from the root
+            if let Some(chain) = self.chain[0].ancestry(&entry.path_string) {
+                self.chain = chain;
+                self.files = count_files(entry);
+                self.selected = 0;
+                self.offset = 0;
+            }
+        }
+    }
+
+    fn ascend(&mut self) {
+        if self.chain.len() > 1 {
+            let previous = self.chain.pop().expect("chain has more than one entry");
+            self.files = count_files(self.current());
+            self.selected = self.rows().iter().position(|row| std::ptr::eq(row.entry, previous)).unwrap_or(0);
+        }
+    }
+
+    fn draw(&mut self, out: &mut Stdout, rows: &[Row<'a>]) -> crossterm::Result<()> {
+        let (width, height) = terminal::size()?;
+        let (width, list_height) = (width as usize, height.saturating_sub(2) as usize);
+
+        // Keep the selection on screen
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if list_height > 0 && self.selected >= self.offset + list_height {
+            self.offset = self.selected + 1 - list_height;
+        }
+
+        let current = self.current();
+        queue!(out, terminal::Clear(terminal::ClearType::All), cursor::MoveTo(0, 0),
+            SetAttribute(Attribute::Bold), Print(pad_str(&current.path_string, width, Alignment::Left, Some("…"))), SetAttribute(Attribute::Reset))?;
+
+        let name_width = width.saturating_sub(BAR_WIDTH + 20);
+        for (line, (idx, row)) in rows.iter().enumerate().skip(self.offset).take(list_height).enumerate() {
+            let fraction = row.fraction();
+            let expander = match (row.entry.children(), self.expanded.contains(&row.entry.path_string)) {
+                (None, _) => ' ',
+                (Some(_), true) => '▾',
+                (Some(_), false) => '▸',
+            };
+            let name = format!("{}{} {}", "  ".repeat(row.depth), expander, row.entry.identifier);
+            let text = format!("{} {} {:>5.1}% {:>10}",
+                pad_str(&name, name_width, Alignment::Left, Some("…")),
+                bar(fraction, BAR_WIDTH),
+                fraction * 100.0,
+                row.entry.len.bytes_to_readable());
+
+            queue!(out, cursor::MoveTo(0, line as u16 + 1))?;
+            if idx == self.selected {
+                queue!(out, SetBackgroundColor(Color::DarkBlue), SetForegroundColor(Color::White))?;
+            }
+            queue!(out, Print(pad_str(&text, width, Alignment::Left, None)), ResetColor)?;
+        }
+
+        let status = format!(" {} in {} files, {} items | ↑↓ move  → expand  ← collapse  Enter open  Backspace up  q REPL",
+            current.len.bytes_to_readable(), self.files, rows.len());
+        queue!(out, cursor::MoveTo(0, height.saturating_sub(1)), SetAttribute(Attribute::Reverse),
+            Print(pad_str(&status, width, Alignment::Left, Some("…"))), SetAttribute(Attribute::Reset))?;
+        out.flush()?;
+        Ok(())
+    }
+
+    fn run(&mut self, out: &mut Stdout) -> crossterm::Result<()> {
+        loop {
+            let rows = self.rows();
+            self.selected = self.selected.min(rows.len().saturating_sub(1));
+            self.draw(out, &rows)?;
+
+            let page = terminal::size()?.1.saturating_sub(2).max(1) as usize;
+            let key = match event::read()? {
+                Event::Key(key) => key,
+                _ => continue,
+            };
+            match key.code {
+                KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+                KeyCode::Down => self.selected += 1,
+                KeyCode::PageUp => self.selected = self.selected.saturating_sub(page),
+                KeyCode::PageDown => self.selected += page,
+                KeyCode::Home => self.selected = 0,
+                KeyCode::End => self.selected = rows.len().saturating_sub(1),
+                KeyCode::Right | KeyCode::Char('+') => {
+                    if let Some(row) = rows.get(self.selected) {
+                        if row.entry.children().is_some() {
+                            self.expanded.insert(row.entry.path_string.to_string());
+                        }
+                    }
+                },
+                KeyCode::Left | KeyCode::Char('-') => {
+                    if let Some(row) = rows.get(self.selected) {
+                        if !self.expanded.remove(&row.entry.path_string) && row.depth > 0 {
+                            // Already collapsed, move to the enclosing directory instead
+                            self.selected = rows[..self.selected].iter().rposition(|parent| parent.depth < row.depth).unwrap_or(0);
+                        }
+                    }
+                },
+                KeyCode::Enter => {
+                    if let Some(row) = rows.get(self.selected) {
+                        self.descend(row.entry);
+                    }
+                },
+                KeyCode::Backspace => self.ascend(),
+                KeyCode::Esc | KeyCode::Char('q') => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Full screen browser over the scanned tree, starting at the last directory of chain. Returns
+/// the chain of the directory that was open when the user left so the REPL can continue there.
+pub fn browse<'a>(chain: Vec<&'a FileSystemEntry>, order: &SortOrder) -> crossterm::Result<Vec<&'a FileSystemEntry>> {
+    let mut browser = Browser::new(chain, order);
+    fullscreen(|out| browser.run(out))?;
+    Ok(browser.chain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> FileSystemEntry {
+        FileSystemEntry::from_paths("/r", &[
+            ("/r/a", FileSystemEntryType::Directory, 0),
+            ("/r/a/b", FileSystemEntryType::Directory, 0),
+            ("/r/a/b/big", FileSystemEntryType::File, 600),
+            ("/r/a/small", FileSystemEntryType::File, 200),
+            ("/r/c", FileSystemEntryType::File, 200),
+        ])
+    }
+
+    fn listed<'a>(rows: &[Row<'a>]) -> Vec<(&'a str, usize, String)> {
+        rows.iter().map(|row| (row.entry.identifier.as_str(), row.depth, format!("{:.1}", row.fraction() * 100.0))).collect()
+    }
+
+    #[test]
+    fn test_rows() {
+        let root = tree();
+        let mut browser = Browser::new(vec![&root], &SortOrder::default());
+        assert_eq!(listed(&browser.rows()), vec![("a", 0, String::from("80.0")), ("c", 0, String::from("20.0"))]);
+
+        browser.expanded.insert(String::from("/r/a"));
+        browser.expanded.insert(String::from("/r/a/b"));
+        assert_eq!(listed(&browser.rows()), vec![
+            ("a", 0, String::from("80.0")),
+            ("b", 1, String::from("75.0")),
+            ("big", 2, String::from("100.0")),
+            ("small", 1, String::from("25.0")),
+            ("c", 0, String::from("20.0")),
+        ]);
+    }
+
+    #[test]
+    fn test_descend_and_ascend() {
+        let root = tree();
+        let mut browser = Browser::new(vec![&root], &SortOrder::default());
+        assert_eq!(browser.files, 3);
+        browser.expanded.insert(String::from("/r/a"));
+        let rows = browser.rows();
+
+        // Files can't be entered, nested directories rebuild the chain from the root
+        browser.descend(rows[3].entry);
+        assert_eq!(browser.current().path_string, "/r");
+        browser.descend(rows[1].entry);
+        assert_eq!(browser.chain.len(), 3);
+        assert_eq!(browser.files, 1);
+        assert_eq!(listed(&browser.rows()), vec![("big", 0, String::from("100.0"))]);
+
+        browser.ascend();
+        assert_eq!(browser.current().path_string, "/r/a");
+        assert_eq!((browser.selected, browser.files), (0, 2));
+        browser.ascend();
+        browser.ascend();
+        assert_eq!(browser.current().path_string, "/r");
+        assert_eq!(browser.selected, 0);
+    }
+}
@@ -1,39 +1,97 @@
-use std::io::{Write};
-use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+use std::io::{IsTerminal, Write};
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use termcolor::{ColorChoice, ColorSpec, StandardStream, WriteColor};
+
+pub use termcolor::Color;
+
+/// Colors of the log functions
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Palette {
+    pub normal: Color,
+    pub info: Color,
+    pub success: Color,
+    pub warning: Color,
+    pub error: Color,
+}
+
+impl Palette {
+    pub const DEFAULT: Palette = Palette {
+        normal: Color::White,
+        info: Color::Blue,
+        success: Color::Green,
+        warning: Color::Yellow,
+        error: Color::Red,
+    };
+}
+
+impl Default for Palette {
+    fn default() -> Palette {
+        Palette::DEFAULT
+    }
+}
+
+static PALETTE: RwLock<Palette> = RwLock::new(Palette::DEFAULT);
+
+pub fn set_palette(palette: Palette) {
+    *PALETTE.write().expect("Palette lock poisoned") = palette;
+}
+
+fn palette() -> Palette {
+    *PALETTE.read().expect("Palette lock poisoned")
+}
+
+static TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Sends all further log output to stderr, keeping stdout free for machine readable output.
+/// Colors are only used when stderr is a terminal.
+pub fn log_to_stderr() {
+    TO_STDERR.store(true, Ordering::Relaxed);
+}
+
+fn log_stream() -> StandardStream {
+    if TO_STDERR.load(Ordering::Relaxed) {
+        let choice = if std::io::stderr().is_terminal() { ColorChoice::Always } else { ColorChoice::Never };
+        StandardStream::stderr(choice)
+    } else {
+        let choice = if std::io::stdout().is_terminal() { ColorChoice::Always } else { ColorChoice::Never };
+        StandardStream::stdout(choice)
+    }
+}
 
 fn color_log(msg: &str, color: Color) -> std::io::Result<()> {
-    let mut stdout = StandardStream::stdout(ColorChoice::Always);
+    let mut stdout = log_stream();
     stdout.set_color(ColorSpec::new().set_fg(Some(color)))?;
     writeln!(&mut stdout, "{}", msg)
 }
 
 fn reset_color() -> std::io::Result<()> {
-    let mut stdout = StandardStream::stdout(ColorChoice::Always);
-    stdout.set_color(ColorSpec::new().set_fg(Some(Color::White)))
+    let mut stdout = log_stream();
+    stdout.set_color(ColorSpec::new().set_fg(Some(palette().normal)))
 }
 
 pub fn log(msg: &str) {
-    color_log(msg, Color::White).expect("Failed to log");
+    color_log(msg, palette().normal).expect("Failed to log");
     reset_color().expect("Failed to reset log");
 }
 
 pub fn log_i(msg: &str) {
-    color_log(msg, Color::Blue).expect("Failed to log");
+    color_log(msg, palette().info).expect("Failed to log");
     reset_color().expect("Failed to reset log");
 }
 
 pub fn log_s(msg: &str) {
-    color_log(msg, Color::Green).expect("Failed to log");
+    color_log(msg, palette().success).expect("Failed to log");
     reset_color().expect("Failed to reset log");
 }
 
 pub fn log_w(msg: &str) {
-    color_log(msg, Color::Yellow).expect("Failed to log");
+    color_log(msg, palette().warning).expect("Failed to log");
     reset_color().expect("Failed to reset log");
 }
 
 pub fn log_e(msg: &str) {
-    color_log(msg, Color::Red).expect("Failed to log");
+    color_log(msg, palette().error).expect("Failed to log");
     reset_color().expect("Failed to reset log");
 }
 